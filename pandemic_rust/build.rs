@@ -8,21 +8,52 @@ use std::io::Read;
 use std::io::Write;
 use std::path::Path;
 
+#[path = "src/map_validation.rs"]
+mod map_validation;
+
+use map_validation::{
+    diseases_of_cities, validate_map, MapCitySpec, MapSpec, DEFAULT_DISEASE_CUBES,
+};
+
+// where every pawn and the first research station start on the bundled map
+const STARTING_CITY: &str = "Atlanta";
+const STANDARD_MAP: &str = "standard";
+// a map file, e.g. one written by pandemic-mapgen, to build in instead of the bundled map
+const MAP_ENV_VAR: &str = "PANDEMIC_MAP";
+// the bundled map's diseases, in the order of their ids
+const DISEASES: [&str; 4] = ["Blue", "Red", "Black", "Yellow"];
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 fn camel_case(s: String) -> String {
     let re = Regex::new(r"(_|-)+").unwrap();
     let s = re.replace_all(&s, " ").to_string();
-    let mut words = s.split_whitespace().map(str::to_owned);
-    let first_word = words.next().unwrap_or_default();
-    let mut upper_camel_first_word = "".to_owned();
-    if !first_word.is_empty() {
-        upper_camel_first_word.push_str(&first_word[..1].to_uppercase());
-        upper_camel_first_word.push_str(&first_word[1..]);
+    s.split_whitespace().map(capitalize).collect()
+}
+
+// the name of a city's CityCard const: its ASCII letters and digits, camel cased
+fn identifier(name: &str) -> String {
+    let ident: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .map(capitalize)
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident
+    } else {
+        format!("City{}", ident)
     }
-    words.fold(upper_camel_first_word, |mut acc, word| {
-        acc.push_str(&word[..1].to_uppercase());
-        acc.push_str(&word[1..]);
-        acc
-    })
+}
+
+// generated files go to OUT_DIR, leaving the source tree as it is
+fn out_path(file: &str) -> String {
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    Path::new(&out_dir).join(file).to_str().unwrap().to_string()
 }
 
 fn recreate_city_graph_with_upper_camel_case(original_json_file: String, output_json_file: String) {
@@ -102,21 +133,176 @@ fn convert_snake_case_city_disease_to_upper_camel_case(
         .collect()
 }
 
-#[derive(Deserialize, Serialize)]
-struct TemplateData {
-    first_variant: String,
+// the parts of board::MapFile the build uses
+#[derive(Deserialize)]
+struct MapFileCity {
+    name: String,
+    disease: String,
+    neighbors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct MapFileDisease {
+    name: String,
+    cubes: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct MapFile {
+    name: String,
+    starting_city: String,
+    #[serde(default)]
+    diseases: Vec<MapFileDisease>,
+    cities: Vec<MapFileCity>,
+}
+
+struct BuiltMap {
+    name: String,
+    starting_city: String,
+    // names and cube supplies, in the order of their ids
+    diseases: Vec<TemplateDisease>,
+    // cities in the order of their ids
+    names: Vec<String>,
     city_graph: HashMap<String, Vec<String>>,
-    city_graph_keys: Vec<String>,
     city_disease: HashMap<String, String>,
 }
+
+fn load_map_file(file: &str) -> BuiltMap {
+    let mut json_blob = String::new();
+    File::open(file)
+        .and_then(|mut f| f.read_to_string(&mut json_blob))
+        .unwrap_or_else(|e| panic!("can't read {} from {}: {}", file, MAP_ENV_VAR, e));
+    let map: MapFile = serde_json::from_str(&json_blob)
+        .unwrap_or_else(|e| panic!("{} is not a map file: {}", file, e));
+    let diseases = if map.diseases.is_empty() {
+        diseases_of_cities(map.cities.iter().map(|c| c.disease.as_str()))
+            .into_iter()
+            .map(|name| TemplateDisease {
+                name: name.to_string(),
+                cubes: DEFAULT_DISEASE_CUBES,
+            })
+            .collect()
+    } else {
+        map.diseases
+            .into_iter()
+            .map(|d| TemplateDisease {
+                name: d.name,
+                cubes: d.cubes.unwrap_or(DEFAULT_DISEASE_CUBES),
+            })
+            .collect()
+    };
+    let mut names = Vec::new();
+    let mut city_graph = HashMap::new();
+    let mut city_disease = HashMap::new();
+    // ids follow the file, like they do for Board::load
+    for city in map.cities {
+        if city_graph.contains_key(&city.name) {
+            panic!("{} has two cities named {}", file, city.name);
+        }
+        names.push(city.name.clone());
+        city_graph.insert(city.name.clone(), city.neighbors);
+        city_disease.insert(city.name, city.disease);
+    }
+    BuiltMap {
+        name: map.name,
+        starting_city: map.starting_city,
+        diseases,
+        names,
+        city_graph,
+        city_disease,
+    }
+}
+
+// the same checks the runtime loader makes, so a bad edit to the json files fails the build
+fn check_bundled_map(
+    starting_city: &str,
+    diseases: &[TemplateDisease],
+    names: &[String],
+    city_graph: &HashMap<String, Vec<String>>,
+    city_disease: &HashMap<String, String>,
+) {
+    let spec = MapSpec {
+        starting_city,
+        cities: names
+            .iter()
+            .map(|name| MapCitySpec {
+                name,
+                disease: city_disease.get(name).map(|d| d.as_str()),
+                neighbors: city_graph[name].iter().map(|n| n.as_str()).collect(),
+            })
+            .collect(),
+        diseases: diseases.iter().map(|d| d.name.as_str()).collect(),
+    };
+    let mut problems = validate_map(&spec);
+    let mut extra: Vec<&String> = city_disease
+        .keys()
+        .filter(|city| !city_graph.contains_key(*city))
+        .collect();
+    extra.sort();
+    for city in extra {
+        problems.push(format!(
+            "{} has a disease color but is not in the city graph",
+            city
+        ));
+    }
+    // each city becomes a CityCard const
+    let mut identifiers: HashMap<String, &String> = HashMap::new();
+    for name in names {
+        if let Some(other) = identifiers.insert(identifier(name), name) {
+            problems.push(format!(
+                "{} and {} would both be CityCard::{}",
+                other,
+                name,
+                identifier(name)
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        panic!("invalid bundled map:\n  {}", problems.join("\n  "));
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct TemplateDisease {
+    name: String,
+    cubes: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TemplateCity {
+    name: String,
+    // of the CityCard const
+    identifier: String,
+    // index into TemplateData::diseases
+    disease: usize,
+    neighbors: Vec<String>,
+}
+
+// Strings that end up inside Rust string literals, like map_name, are rendered as complete
+// literals with {:?} and put in the template unescaped
+#[derive(Deserialize, Serialize)]
+struct TemplateData {
+    map_name: String,
+    ndiseases: usize,
+    diseases: Vec<TemplateDisease>,
+    ncities: usize,
+    cities: Vec<TemplateCity>,
+    // identifier of the starting city's CityCard const
+    starting_city: String,
+}
 fn create_city_graph_mod(
-    input_city_graph_json_file: String,
-    input_city_disease_json_file: String,
+    map: BuiltMap,
     input_city_graph_hbs_template_file: String,
     output_city_graph_rust_file: String,
 ) {
-    let city_graph = load_json_city_graph(input_city_graph_json_file);
-    let city_disease = load_json_city_disease(input_city_disease_json_file);
+    let BuiltMap {
+        name: map_name,
+        starting_city,
+        diseases,
+        names,
+        city_graph,
+        city_disease,
+    } = map;
 
     let handlebars = Handlebars::new();
 
@@ -126,13 +312,38 @@ fn create_city_graph_mod(
         .read_to_string(&mut city_graph_template_str)
         .unwrap();
 
-    let mut city_graph_keys: Vec<String> = city_graph.keys().map(|s| s.to_string()).collect();
-    let first_variant = city_graph_keys.pop().unwrap();
+    check_bundled_map(
+        &starting_city,
+        &diseases,
+        &names,
+        &city_graph,
+        &city_disease,
+    );
+    let cities = names
+        .into_iter()
+        .map(|name| TemplateCity {
+            disease: diseases
+                .iter()
+                .position(|d| d.name.eq_ignore_ascii_case(&city_disease[&name]))
+                .unwrap(),
+            neighbors: city_graph[&name].iter().map(|n| identifier(n)).collect(),
+            identifier: identifier(&name),
+            name: format!("{:?}", name),
+        })
+        .collect::<Vec<TemplateCity>>();
     let template_data = TemplateData {
-        first_variant,
-        city_graph_keys,
-        city_graph,
-        city_disease,
+        map_name: format!("{:?}", map_name),
+        ndiseases: diseases.len(),
+        diseases: diseases
+            .into_iter()
+            .map(|d| TemplateDisease {
+                name: format!("{:?}", d.name),
+                cubes: d.cubes,
+            })
+            .collect(),
+        ncities: cities.len(),
+        cities,
+        starting_city: identifier(&starting_city),
     };
     let output_rust_mod = handlebars
         .render_template(&city_graph_template_str, &template_data)
@@ -144,19 +355,57 @@ fn create_city_graph_mod(
 }
 
 fn main() {
+    for file in [
+        "build.rs",
+        "src/city_graph.json",
+        "src/city_diseases.json",
+        "src/city_graph_template.rs.hbs",
+        "src/map_validation.rs",
+    ] {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+    println!("cargo:rerun-if-env-changed={}", MAP_ENV_VAR);
+
     recreate_city_graph_with_upper_camel_case(
         "src/city_graph.json".to_string(),
-        "src/city_graph_upper_camel_case.json".to_string(),
+        out_path("city_graph_upper_camel_case.json"),
     );
 
     recreate_city_disease_with_upper_camel_case(
         "src/city_diseases.json".to_string(),
-        "src/city_diseases_upper_camel_case.json".to_string(),
+        out_path("city_diseases_upper_camel_case.json"),
     );
 
+    let map = match env::var(MAP_ENV_VAR) {
+        Result::Ok(file) => {
+            println!("cargo:rerun-if-changed={}", file);
+            load_map_file(&file)
+        }
+        Result::Err(_) => {
+            let city_graph = load_json_city_graph(out_path("city_graph_upper_camel_case.json"));
+            // sorted so city ids don't depend on HashMap iteration order
+            let mut names: Vec<String> = city_graph.keys().map(|s| s.to_string()).collect();
+            names.sort();
+            BuiltMap {
+                name: STANDARD_MAP.to_string(),
+                starting_city: STARTING_CITY.to_string(),
+                diseases: DISEASES
+                    .iter()
+                    .map(|name| TemplateDisease {
+                        name: name.to_string(),
+                        cubes: DEFAULT_DISEASE_CUBES,
+                    })
+                    .collect(),
+                names,
+                city_graph,
+                city_disease: load_json_city_disease(out_path(
+                    "city_diseases_upper_camel_case.json",
+                )),
+            }
+        }
+    };
     create_city_graph_mod(
-        "src/city_graph_upper_camel_case.json".to_string(),
-        "src/city_diseases_upper_camel_case.json".to_string(),
+        map,
         "src/city_graph_template.rs.hbs".to_string(),
         "city_graph.rs".to_string(),
     );
@@ -0,0 +1,16 @@
+use pandemic_rust::interactive::parse_command;
+use pandemic_rust::pandemic_game::{PandemicGameConfig, PandemicGameState};
+use pandemic_rust::scheduler::{EventWindow, PendingDecision};
+fn main() {
+    let state = PandemicGameState::new(PandemicGameConfig::builder().seed(0).build().unwrap());
+    let pending = state.next_decision();
+    println!("{:?}", pending);
+    for line in ["build foo", "build", "build from paris", "build foo from paris", "show x", "pass now", "help", "event quiet now"] {
+        println!("{:?} -> {:?}", line, parse_command(&state, &pending, &[0, 1, 2, 3], line));
+    }
+    for i in 0..4 { println!("{} {:?}", i, state.playable_events(i)); }
+    let window = PendingDecision::ChooseEvent { agent_idxs: vec![], window: EventWindow::BeforeInfect };
+    for line in ["event airlift 0 paris", "event quiet", "event forecast"] {
+        println!("{:?} -> {:?}", line, parse_command(&state, &window, &[0, 1, 2, 3], line));
+    }
+}
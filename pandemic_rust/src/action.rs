@@ -0,0 +1,240 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, CityCard, NamedOnBoard};
+use crate::game_enums::{Disease, EventCard};
+
+// Moves the Dispatcher can make on behalf of another pawn. DispatchFlight moves the pawn to a
+// city that already contains another pawn, and is only valid as part of Action::DispatchMove
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+pub enum MoveAction {
+    Drive(CityCard),
+    DirectFlight(CityCard),
+    CharterFlight(CityCard),
+    ShuttleFlight(CityCard),
+    DispatchFlight(CityCard),
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum EventAction {
+    Airlift {
+        agent_idx: usize,
+        city: CityCard,
+    },
+    // relocate_from works like it does for Action::BuildResearchStation
+    GovernmentGrant {
+        city: CityCard,
+        relocate_from: Option<CityCard>,
+    },
+    ResilientPopulation(CityCard),
+    // new order of the top 6 infection cards, top first, as indices into the forecasted cards
+    // (also listed top first)
+    Forecast(Vec<usize>),
+    OneQuietNight,
+}
+
+impl MoveAction {
+    pub fn city(&self) -> CityCard {
+        match self {
+            MoveAction::Drive(city)
+            | MoveAction::DirectFlight(city)
+            | MoveAction::CharterFlight(city)
+            | MoveAction::ShuttleFlight(city)
+            | MoveAction::DispatchFlight(city) => *city,
+        }
+    }
+}
+
+impl EventAction {
+    // every city the event names, which all need to be on the board
+    pub fn cities(&self) -> Vec<CityCard> {
+        match self {
+            EventAction::Airlift { city, .. } | EventAction::ResilientPopulation(city) => {
+                vec![*city]
+            }
+            EventAction::GovernmentGrant {
+                city,
+                relocate_from,
+            } => std::iter::once(*city).chain(*relocate_from).collect(),
+            EventAction::Forecast(_) | EventAction::OneQuietNight => vec![],
+        }
+    }
+
+    pub fn card(&self) -> EventCard {
+        match self {
+            EventAction::Airlift { .. } => EventCard::Airlift,
+            EventAction::GovernmentGrant { .. } => EventCard::GovernmentGrant,
+            EventAction::ResilientPopulation(_) => EventCard::ResilientPopulation,
+            EventAction::Forecast(_) => EventCard::Forecast,
+            EventAction::OneQuietNight => EventCard::OneQuietNight,
+        }
+    }
+}
+
+// A single move by the current player, carrying everything needed to validate and replay it.
+// Agent indices refer to PandemicGameState::agents
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum Action {
+    Drive(CityCard),
+    DirectFlight(CityCard),
+    CharterFlight(CityCard),
+    ShuttleFlight(CityCard),
+    TreatDisease(Disease),
+    // relocate_from is the station to move here once all of them are on the board, and must be
+    // None before that
+    BuildResearchStation {
+        relocate_from: Option<CityCard>,
+    },
+    ShareKnowledge {
+        giving_agent_idx: usize,
+        receiving_agent_idx: usize,
+        city: CityCard,
+    },
+    DiscoverCure {
+        disease: Disease,
+        cards: Vec<CityCard>,
+    },
+    DispatchMove {
+        agent_idx: usize,
+        move_action: MoveAction,
+    },
+    OperationsMove {
+        city: CityCard,
+        discard: CityCard,
+    },
+    ContingencyPlan(EventCard),
+    // events don't use up an action, and can be played by any agent
+    PlayEvent {
+        agent_idx: usize,
+        event: EventAction,
+    },
+}
+
+impl Action {
+    pub fn uses_action(&self) -> bool {
+        !matches!(self, Action::PlayEvent { .. })
+    }
+
+    // the disease the action names, which needs to be on the board
+    pub fn disease(&self) -> Option<Disease> {
+        match self {
+            Action::TreatDisease(disease) | Action::DiscoverCure { disease, .. } => Some(*disease),
+            _ => None,
+        }
+    }
+
+    // every city the action names, which all need to be on the board
+    pub fn cities(&self) -> Vec<CityCard> {
+        match self {
+            Action::Drive(city)
+            | Action::DirectFlight(city)
+            | Action::CharterFlight(city)
+            | Action::ShuttleFlight(city)
+            | Action::ShareKnowledge { city, .. } => vec![*city],
+            Action::BuildResearchStation { relocate_from } => {
+                relocate_from.iter().copied().collect()
+            }
+            Action::DiscoverCure { cards, .. } => cards.clone(),
+            Action::DispatchMove { move_action, .. } => vec![move_action.city()],
+            Action::OperationsMove { city, discard } => vec![*city, *discard],
+            Action::PlayEvent { event, .. } => event.cities(),
+            Action::TreatDisease(_) | Action::ContingencyPlan(_) => vec![],
+        }
+    }
+}
+
+impl NamedOnBoard for MoveAction {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            MoveAction::Drive(_) => "Drive",
+            MoveAction::DirectFlight(_) => "DirectFlight",
+            MoveAction::CharterFlight(_) => "CharterFlight",
+            MoveAction::ShuttleFlight(_) => "ShuttleFlight",
+            MoveAction::DispatchFlight(_) => "DispatchFlight",
+        };
+        write!(f, "{}({})", kind, board.named(&self.city()))
+    }
+}
+
+impl NamedOnBoard for EventAction {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventAction::Airlift { agent_idx, city } => write!(
+                f,
+                "Airlift {{ agent_idx: {}, city: {} }}",
+                agent_idx,
+                board.named(city)
+            ),
+            EventAction::GovernmentGrant {
+                city,
+                relocate_from,
+            } => write!(
+                f,
+                "GovernmentGrant {{ city: {}, relocate_from: {} }}",
+                board.named(city),
+                board.named(relocate_from)
+            ),
+            EventAction::ResilientPopulation(city) => {
+                write!(f, "ResilientPopulation({})", board.named(city))
+            }
+            EventAction::Forecast(_) | EventAction::OneQuietNight => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl NamedOnBoard for Action {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Drive(city) => write!(f, "Drive({})", board.named(city)),
+            Action::DirectFlight(city) => write!(f, "DirectFlight({})", board.named(city)),
+            Action::CharterFlight(city) => write!(f, "CharterFlight({})", board.named(city)),
+            Action::ShuttleFlight(city) => write!(f, "ShuttleFlight({})", board.named(city)),
+            Action::TreatDisease(disease) => write!(f, "TreatDisease({})", board.named(disease)),
+            Action::BuildResearchStation { relocate_from } => write!(
+                f,
+                "BuildResearchStation {{ relocate_from: {} }}",
+                board.named(relocate_from)
+            ),
+            Action::ShareKnowledge {
+                giving_agent_idx,
+                receiving_agent_idx,
+                city,
+            } => write!(
+                f,
+                "ShareKnowledge {{ giving_agent_idx: {}, receiving_agent_idx: {}, city: {} }}",
+                giving_agent_idx,
+                receiving_agent_idx,
+                board.named(city)
+            ),
+            Action::DiscoverCure { disease, cards } => write!(
+                f,
+                "DiscoverCure {{ disease: {}, cards: {} }}",
+                board.named(disease),
+                board.named(cards)
+            ),
+            Action::DispatchMove {
+                agent_idx,
+                move_action,
+            } => write!(
+                f,
+                "DispatchMove {{ agent_idx: {}, move_action: {} }}",
+                agent_idx,
+                board.named(move_action)
+            ),
+            Action::OperationsMove { city, discard } => write!(
+                f,
+                "OperationsMove {{ city: {}, discard: {} }}",
+                board.named(city),
+                board.named(discard)
+            ),
+            Action::ContingencyPlan(_) => write!(f, "{:?}", self),
+            Action::PlayEvent { agent_idx, event } => write!(
+                f,
+                "PlayEvent {{ agent_idx: {}, event: {} }}",
+                agent_idx,
+                board.named(event)
+            ),
+        }
+    }
+}
@@ -1,24 +1,26 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, EnumString};
+
 use crate::game_enums::GameEnd;
-use crate::pandemic_game::PandemicGameState;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnError {
     pub msg: String,
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionError {
     pub msg: String,
 }
 // TODO: these can be done better. Ok?
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum TurnEndState {
     TErr(TurnError),
     AErr(ActionError),
     Ok(GameEnd),
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum ActionEndState {
     Err(ActionError),
     Ok(GameEnd),
@@ -47,7 +49,19 @@ impl fmt::Display for ActionError {
     }
 }
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, FromPrimitive)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Hash,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    EnumIter,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
 pub enum AgentName {
     Contingency,
     Dispatcher,
@@ -64,63 +78,12 @@ impl AgentName {
     }
 }
 
-pub type ActionFn<'a> = &'a dyn Fn(&mut PandemicGameState, usize) -> Result<(), ActionEndState>;
-
-pub struct Agent<'a> {
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Agent {
     pub agent_type: AgentName,
-    pub actions: Vec<ActionFn<'a>>,
 }
-impl<'a> Agent<'a> {
+impl Agent {
     pub fn new(agent_type: AgentName) -> Self {
-        let actions = match agent_type {
-            AgentName::Contingency => {
-                let contingency_plan: ActionFn =
-                    &|state: &mut PandemicGameState, agent_idx: usize| {
-                        state.contingency_plan(agent_idx)
-                    };
-                vec![contingency_plan]
-            }
-            AgentName::Dispatcher => {
-                let dispatch_flight: ActionFn =
-                    &|state: &mut PandemicGameState, agent_idx: usize| {
-                        state.dispatch_flight(agent_idx)
-                    };
-                let charter_flight: ActionFn =
-                    &|state: &mut PandemicGameState, agent_idx: usize| {
-                        state.charter_flight(agent_idx)
-                    };
-                let direct_flight: ActionFn = &|state: &mut PandemicGameState, agent_idx: usize| {
-                    state.direct_flight(agent_idx)
-                };
-                let dispatch_move: ActionFn = &|state: &mut PandemicGameState, agent_idx: usize| {
-                    state.dispatch_move(agent_idx)
-                };
-                vec![
-                    dispatch_flight,
-                    charter_flight,
-                    direct_flight,
-                    dispatch_move,
-                ]
-            }
-            AgentName::Operations => {
-                let operations_move: ActionFn =
-                    &|state: &mut PandemicGameState, agent_idx: usize| {
-                        state.operations_move(agent_idx)
-                    };
-                vec![operations_move]
-            }
-            AgentName::Researcher => {
-                let share_knowledge: ActionFn =
-                    &|state: &mut PandemicGameState, agent_idx: usize| {
-                        state.share_knowledge(agent_idx)
-                    };
-                vec![share_knowledge]
-            }
-            _ => vec![],
-        };
-        Self {
-            agent_type,
-            actions,
-        }
+        Self { agent_type }
     }
 }
@@ -0,0 +1,95 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+use pandemic_rust::map_generator::{generate_map, MapParams};
+
+const USAGE: &str = "usage: pandemic-mapgen [options]
+
+Writes a random playable map in the format --map and PANDEMIC_MAP take.
+
+  --cities N         number of cities (default 48)
+  --colors N         number of disease colors (default 4)
+  --degree F         average number of neighbors per city (default 3.875)
+  --clustering F     0 to 1, how strongly cities link to their closest neighbors (default 0.8)
+  --crossing         let edges cross, the map is planar otherwise
+  --ocean N          long edges between far apart cities (default 3)
+  --scattered        scatter each color over the map instead of growing it as one region
+  --name NAME        map name results are recorded under (default generated-SEED)
+  --seed N           seed the map is drawn from (default 0)
+  --out FILE         write the map to FILE instead of stdout";
+
+struct Args {
+    params: MapParams,
+    name: Option<String>,
+    out: Option<String>,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        params: MapParams::default(),
+        name: None,
+        out: None,
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        let params = &mut args.params;
+        match flag.as_str() {
+            "--cities" => params.ncities = parse_value(&flag, argv.next())?,
+            "--colors" => params.ncolors = parse_value(&flag, argv.next())?,
+            "--degree" => params.average_degree = parse_value(&flag, argv.next())?,
+            "--clustering" => params.clustering = parse_value(&flag, argv.next())?,
+            "--crossing" => params.planar = false,
+            "--ocean" => params.ocean_edges = parse_value(&flag, argv.next())?,
+            "--scattered" => params.contiguous_colors = false,
+            "--name" => args.name = Some(parse_value(&flag, argv.next())?),
+            "--seed" => params.seed = parse_value(&flag, argv.next())?,
+            "--out" => args.out = Some(parse_value(&flag, argv.next())?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Result::Err(format!("unknown option {}", other)),
+        }
+    }
+    args.params.name = match &args.name {
+        Some(name) => name.clone(),
+        None => format!("generated-{}", args.params.seed),
+    };
+    Result::Ok(args)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Result::Ok(args) => args,
+        Result::Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let board = match generate_map(&args.params) {
+        Result::Ok(board) => board,
+        Result::Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let json = board.to_json();
+    match &args.out {
+        Some(path) => {
+            if let Result::Err(e) = fs::write(path, json + "\n") {
+                eprintln!("can't write {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+        None => println!("{}", json),
+    }
+}
@@ -0,0 +1,224 @@
+use std::env;
+use std::io;
+use std::process;
+use std::str::FromStr;
+
+use pandemic_rust::agent::AgentName;
+use pandemic_rust::board::Board;
+use pandemic_rust::controller::{Controller, RandomController};
+use pandemic_rust::interactive::TerminalClient;
+use pandemic_rust::mcts::{MctsAI, SearchBudget};
+use pandemic_rust::pandemic_game::{
+    PandemicGameConfig, PandemicGameConfigBuilder, PandemicGameState,
+};
+use pandemic_rust::rollout_ai::SingleAgentRolloutAI;
+use pandemic_rust::ruleset::{parse_roles, Difficulty, StartingPlayer};
+
+const USAGE: &str = "usage: pandemic-play [options]
+
+  --players N        2 to 4 players (default 4)
+  --map FILE         play on a map file instead of the standard board
+  --humans I,J,...   seats played at the terminal (default all)
+  --bot NAME         random, rollout or mcts, plays the other seats (default rollout)
+  --difficulty NAME  introductory, standard, heroic or custom (default introductory)
+  --epidemics N      epidemic cards in the player deck, for custom difficulty
+  --draw N           player cards drawn each turn (default 2)
+  --first-player I   seat that takes the first turn (default random)
+  --roles A,B,...    roles to deal from, e.g. Medic,Scientist (default all)
+  --seed N           game seed (default random)
+  --load FILE        resume a game saved with the save command, which keeps its own settings";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum BotKind {
+    Random,
+    Rollout,
+    Mcts,
+}
+
+impl FromStr for BotKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BotKind, String> {
+        match s {
+            "random" => Result::Ok(BotKind::Random),
+            "rollout" => Result::Ok(BotKind::Rollout),
+            "mcts" => Result::Ok(BotKind::Mcts),
+            other => Result::Err(format!("unknown bot {}", other)),
+        }
+    }
+}
+
+struct Args {
+    board: Option<Board>,
+    players: i32,
+    humans: Option<Vec<usize>>,
+    bot: BotKind,
+    difficulty: Option<Difficulty>,
+    epidemics: Option<i32>,
+    draw: Option<u32>,
+    first_player: Option<u32>,
+    roles: Option<Vec<AgentName>>,
+    seed: Option<u64>,
+    load: Option<String>,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+// every seat in humans must be at the table, and listed once
+fn check_humans(humans: &[usize], nplayers: usize) -> Result<(), String> {
+    for (i, seat) in humans.iter().enumerate() {
+        if *seat >= nplayers {
+            return Result::Err(format!(
+                "--humans seat {} isn't at the table, seats are 0 to {}",
+                seat,
+                nplayers - 1
+            ));
+        }
+        if humans[..i].contains(seat) {
+            return Result::Err(format!("--humans seat {} listed twice", seat));
+        }
+    }
+    Result::Ok(())
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        board: None,
+        players: 4,
+        humans: None,
+        bot: BotKind::Rollout,
+        difficulty: None,
+        epidemics: None,
+        draw: None,
+        first_player: None,
+        roles: None,
+        seed: None,
+        load: None,
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        match flag.as_str() {
+            "--players" => args.players = parse_value(&flag, argv.next())?,
+            "--map" => {
+                let path: String = parse_value(&flag, argv.next())?;
+                args.board = Some(Board::load(&path).map_err(|e| e.to_string())?);
+            }
+            "--humans" => {
+                let seats: String = parse_value(&flag, argv.next())?;
+                let seats = seats
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| parse_value(&flag, Some(s.to_string())))
+                    .collect::<Result<Vec<usize>, String>>()?;
+                args.humans = Some(seats);
+            }
+            "--bot" => args.bot = parse_value(&flag, argv.next())?,
+            "--difficulty" => args.difficulty = Some(parse_value(&flag, argv.next())?),
+            "--epidemics" => args.epidemics = Some(parse_value(&flag, argv.next())?),
+            "--draw" => args.draw = Some(parse_value(&flag, argv.next())?),
+            "--first-player" => args.first_player = Some(parse_value(&flag, argv.next())?),
+            "--roles" => {
+                let roles: String = parse_value(&flag, argv.next())?;
+                args.roles = Some(parse_roles(&roles)?);
+            }
+            "--seed" => args.seed = Some(parse_value(&flag, argv.next())?),
+            "--load" => args.load = Some(parse_value(&flag, argv.next())?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Result::Err(format!("unknown option {}", other)),
+        }
+    }
+    if args.load.is_none() {
+        config_builder(&args)
+            .build()
+            .map_err(|e| format!("invalid game: {}", e))?;
+    }
+    Result::Ok(args)
+}
+
+fn config_builder(args: &Args) -> PandemicGameConfigBuilder {
+    let mut builder = PandemicGameConfig::builder().nplayers(args.players);
+    if let Some(board) = &args.board {
+        builder = builder.board(board.clone());
+    }
+    if let Some(difficulty) = args.difficulty {
+        builder = builder.difficulty(difficulty);
+    }
+    if let Some(epidemics) = args.epidemics {
+        builder = builder.nepidemics(epidemics);
+    }
+    if let Some(draw) = args.draw {
+        builder = builder.ncards_to_draw(draw);
+    }
+    if let Some(seat) = args.first_player {
+        builder = builder.starting_player(StartingPlayer::Fixed(seat));
+    }
+    if let Some(roles) = &args.roles {
+        builder = builder.roles(roles);
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
+    builder
+}
+
+fn make_bot(kind: BotKind, seed: u64) -> Box<dyn Controller> {
+    match kind {
+        BotKind::Random => Box::new(RandomController::new(seed)),
+        BotKind::Rollout => Box::new(SingleAgentRolloutAI::new(1, seed)),
+        BotKind::Mcts => Box::new(MctsAI::new(SearchBudget::Iterations(1000), 4, seed)),
+    }
+}
+
+fn main() {
+    let args = match parse_args() {
+        Result::Ok(args) => args,
+        Result::Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let state = match &args.load {
+        Some(path) => match PandemicGameState::load_snapshot(path) {
+            Result::Ok(state) => state,
+            Result::Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        // parse_args already checked this config builds
+        None => PandemicGameState::new(config_builder(&args).build().unwrap()),
+    };
+    // seats are checked against the game, which has its own player count when loaded
+    if let Some(humans) = &args.humans {
+        if let Result::Err(e) = check_humans(humans, state.agents.len()) {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    }
+    let seed = state.config.seed;
+    let ruleset = state.config.ruleset();
+    let bots: Vec<Option<Box<dyn Controller>>> = (0..state.agents.len())
+        .map(|i| match &args.humans {
+            Some(humans) if !humans.contains(&i) => {
+                Some(make_bot(args.bot, seed.wrapping_add(i as u64)))
+            }
+            _ => None,
+        })
+        .collect();
+    println!("{}", ruleset);
+    println!("seed {}, type help for commands", seed);
+    let mut client = TerminalClient::new(state, bots);
+    let stdin = io::stdin();
+    if let Result::Err(e) = client.run(stdin.lock(), io::stdout()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
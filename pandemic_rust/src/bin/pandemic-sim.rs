@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use pandemic_rust::agent::AgentName;
+use pandemic_rust::board::Board;
+use pandemic_rust::controller::{decide, Controller, RandomController};
+use pandemic_rust::game_enums::GameEnd;
+use pandemic_rust::mcts::{MctsAI, SearchBudget};
+use pandemic_rust::pandemic_game::{
+    InfectionReport, PandemicGameConfig, PandemicGameConfigBuilder, PandemicGameState,
+};
+use pandemic_rust::rollout_ai::SingleAgentRolloutAI;
+use pandemic_rust::ruleset::{parse_roles, Difficulty, Ruleset, StartingPlayer};
+use pandemic_rust::scheduler::PendingDecision;
+
+const USAGE: &str = "usage: pandemic-sim [options]
+
+  --games N          number of games to play (default 100)
+  --agent NAME       random, rollout or mcts (default random)
+  --players N        2 to 4 players (default 4)
+  --map FILE         play on a map file instead of the standard board
+  --difficulty NAME  introductory, standard, heroic or custom (default introductory)
+  --epidemics N      epidemic cards in the player deck, for custom difficulty
+  --draw N           player cards drawn each turn (default 2)
+  --first-player I   seat that takes the first turn (default random)
+  --roles A,B,...    roles to deal from, e.g. Medic,Scientist (default all)
+  --seed N           seed of the first game, game i uses seed + i (default 0)
+  --no-events        play without event cards
+  --debug            check the game state's invariants after every step
+  --format FORMAT    table, csv or json (default table)
+  --lookahead N      rollout agent: turns to look ahead (default 1)
+  --iterations N     mcts agent: iterations per action (default 1000)
+  --threads N        mcts agent: search threads (default 4)";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AgentKind {
+    Random,
+    Rollout,
+    Mcts,
+}
+
+impl FromStr for AgentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AgentKind, String> {
+        match s {
+            "random" => Result::Ok(AgentKind::Random),
+            "rollout" => Result::Ok(AgentKind::Rollout),
+            "mcts" => Result::Ok(AgentKind::Mcts),
+            other => Result::Err(format!("unknown agent {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "table" => Result::Ok(Format::Table),
+            "csv" => Result::Ok(Format::Csv),
+            "json" => Result::Ok(Format::Json),
+            other => Result::Err(format!("unknown format {}", other)),
+        }
+    }
+}
+
+struct Args {
+    board: Option<Board>,
+    games: u64,
+    agent: AgentKind,
+    players: i32,
+    difficulty: Option<Difficulty>,
+    epidemics: Option<i32>,
+    draw: Option<u32>,
+    first_player: Option<u32>,
+    roles: Option<Vec<AgentName>>,
+    seed: u64,
+    do_events: bool,
+    debug: bool,
+    format: Format,
+    lookahead: usize,
+    iterations: u64,
+    threads: usize,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        board: None,
+        games: 100,
+        agent: AgentKind::Random,
+        players: 4,
+        difficulty: None,
+        epidemics: None,
+        draw: None,
+        first_player: None,
+        roles: None,
+        seed: 0,
+        do_events: true,
+        debug: false,
+        format: Format::Table,
+        lookahead: 1,
+        iterations: 1000,
+        threads: 4,
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(flag) = argv.next() {
+        match flag.as_str() {
+            "--games" => args.games = parse_value(&flag, argv.next())?,
+            "--agent" => args.agent = parse_value(&flag, argv.next())?,
+            "--players" => args.players = parse_value(&flag, argv.next())?,
+            "--map" => {
+                let path: String = parse_value(&flag, argv.next())?;
+                args.board = Some(Board::load(&path).map_err(|e| e.to_string())?);
+            }
+            "--difficulty" => args.difficulty = Some(parse_value(&flag, argv.next())?),
+            "--epidemics" => args.epidemics = Some(parse_value(&flag, argv.next())?),
+            "--draw" => args.draw = Some(parse_value(&flag, argv.next())?),
+            "--first-player" => args.first_player = Some(parse_value(&flag, argv.next())?),
+            "--roles" => {
+                let roles: String = parse_value(&flag, argv.next())?;
+                args.roles = Some(parse_roles(&roles)?);
+            }
+            "--seed" => args.seed = parse_value(&flag, argv.next())?,
+            "--no-events" => args.do_events = false,
+            "--debug" => args.debug = true,
+            "--format" => args.format = parse_value(&flag, argv.next())?,
+            "--lookahead" => args.lookahead = parse_value(&flag, argv.next())?,
+            "--iterations" => args.iterations = parse_value(&flag, argv.next())?,
+            "--threads" => args.threads = parse_value(&flag, argv.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Result::Err(format!("unknown option {}", other)),
+        }
+    }
+    config_builder(&args)
+        .build()
+        .map_err(|e| format!("invalid game: {}", e))?;
+    Result::Ok(args)
+}
+
+fn config_builder(args: &Args) -> PandemicGameConfigBuilder {
+    let mut builder = PandemicGameConfig::builder()
+        .nplayers(args.players)
+        .do_events(args.do_events)
+        .debug(args.debug);
+    if let Some(board) = &args.board {
+        builder = builder.board(board.clone());
+    }
+    if let Some(difficulty) = args.difficulty {
+        builder = builder.difficulty(difficulty);
+    }
+    if let Some(epidemics) = args.epidemics {
+        builder = builder.nepidemics(epidemics);
+    }
+    if let Some(draw) = args.draw {
+        builder = builder.ncards_to_draw(draw);
+    }
+    if let Some(seat) = args.first_player {
+        builder = builder.starting_player(StartingPlayer::Fixed(seat));
+    }
+    if let Some(roles) = &args.roles {
+        builder = builder.roles(roles);
+    }
+    builder
+}
+
+fn make_agent(args: &Args, seed: u64) -> Box<dyn Controller> {
+    match args.agent {
+        AgentKind::Random => Box::new(RandomController::new(seed)),
+        AgentKind::Rollout => Box::new(SingleAgentRolloutAI::new(args.lookahead, seed)),
+        AgentKind::Mcts => Box::new(MctsAI::new(
+            SearchBudget::Iterations(args.iterations),
+            args.threads,
+            seed,
+        )),
+    }
+}
+
+#[derive(Serialize)]
+struct GameResult {
+    seed: u64,
+    game_end: GameEnd,
+    turns: u32,
+    outbreaks: u32,
+    // turn in which each disease was cured, counting from 1
+    turns_to_cure: BTreeMap<String, Option<u32>>,
+    // outbreaks in each city, including chain outbreaks and outbreaks from epidemics
+    outbreak_cities: BTreeMap<String, u32>,
+}
+
+fn count_outbreaks(
+    state: &PandemicGameState,
+    outbreak_cities: &mut BTreeMap<String, u32>,
+    report: &InfectionReport,
+) {
+    for outbreak in &report.outbreaks {
+        *outbreak_cities
+            .entry(state.city_name(outbreak.city).to_string())
+            .or_insert(0) += 1;
+    }
+}
+
+fn play_game(args: &Args, seed: u64) -> GameResult {
+    // parse_args already checked this config builds
+    let config = config_builder(args).seed(seed).build().unwrap();
+    let mut state = PandemicGameState::new(config);
+    let mut agent = make_agent(args, seed);
+    let mut turns = 0;
+    let board = state.config.board.clone();
+    let mut turns_to_cure: BTreeMap<String, Option<u32>> = board
+        .diseases()
+        .map(|d| (board.disease_name(d).to_string(), None))
+        .collect();
+    let mut outbreak_cities = BTreeMap::new();
+    let mut infection_steps = state.infection_steps;
+    let game_end = loop {
+        let decision = match decide(agent.as_mut(), &state) {
+            Some(decision) => decision,
+            None => match state.next_decision() {
+                PendingDecision::GameOver(game_end) => break game_end,
+                _ => unreachable!(),
+            },
+        };
+        let current_player_i = state.current_player_i;
+        if let Result::Err(e) = state.resolve(decision) {
+            panic!("agent made an invalid decision: {}", e);
+        }
+        for disease in board.diseases() {
+            let turn = turns_to_cure.get_mut(board.disease_name(disease)).unwrap();
+            if turn.is_none() && state.is_cured(disease) {
+                *turn = Some(turns + 1);
+            }
+        }
+        // at most one infect step per decision, as it ends the turn
+        if state.infection_steps != infection_steps {
+            infection_steps = state.infection_steps;
+            count_outbreaks(&state, &mut outbreak_cities, &state.infection_report);
+        }
+        if state.current_player_i != current_player_i {
+            turns += 1;
+        }
+    };
+    for report in &state.epidemic_reports {
+        count_outbreaks(&state, &mut outbreak_cities, &report.infection);
+    }
+    GameResult {
+        seed,
+        game_end,
+        turns: turns + 1,
+        outbreaks: state.outbreaks,
+        turns_to_cure,
+        outbreak_cities,
+    }
+}
+
+#[derive(Serialize)]
+struct CureSummary {
+    cured: u64,
+    avg_turns_to_cure: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    agent: String,
+    ruleset: Ruleset,
+    games: u64,
+    wins: u64,
+    win_rate: f64,
+    losses: BTreeMap<String, u64>,
+    avg_outbreaks: f64,
+    avg_turns: f64,
+    cures: BTreeMap<String, CureSummary>,
+    seconds: f64,
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn summarize(
+    args: &Args,
+    config: &PandemicGameConfig,
+    results: &[GameResult],
+    seconds: f64,
+) -> Summary {
+    let games = results.len() as u64;
+    let count = |game_end: GameEnd| results.iter().filter(|r| r.game_end == game_end).count();
+    let wins = count(GameEnd::Win) as u64;
+    let losses = GameEnd::iter()
+        .filter(|game_end| *game_end != GameEnd::Win)
+        .map(|game_end| (format!("{:?}", game_end), count(game_end) as u64))
+        .collect();
+    let outbreaks: Vec<f64> = results.iter().map(|r| r.outbreaks as f64).collect();
+    let turns: Vec<f64> = results.iter().map(|r| r.turns as f64).collect();
+    let mut cures = BTreeMap::new();
+    for disease in config.board.diseases() {
+        let name = config.board.disease_name(disease).to_string();
+        let cure_turns: Vec<f64> = results
+            .iter()
+            .filter_map(|r| r.turns_to_cure[&name])
+            .map(|turn| turn as f64)
+            .collect();
+        cures.insert(
+            name,
+            CureSummary {
+                cured: cure_turns.len() as u64,
+                avg_turns_to_cure: mean(&cure_turns),
+            },
+        );
+    }
+    Summary {
+        agent: format!("{:?}", args.agent).to_lowercase(),
+        ruleset: config.ruleset(),
+        games,
+        wins,
+        win_rate: wins as f64 / games.max(1) as f64,
+        losses,
+        avg_outbreaks: mean(&outbreaks).unwrap_or(0.0),
+        avg_turns: mean(&turns).unwrap_or(0.0),
+        cures,
+        seconds,
+    }
+}
+
+fn format_option(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.2}", value),
+        None => "-".to_string(),
+    }
+}
+
+fn print_table(summary: &Summary) {
+    println!(
+        "{} agent, {}, {} games in {:.1}s",
+        summary.agent, summary.ruleset, summary.games, summary.seconds
+    );
+    println!(
+        "{:<24}{:>10}",
+        "win rate",
+        format!("{:.3}", summary.win_rate)
+    );
+    println!("{:<24}{:>10}", "wins", summary.wins);
+    for (game_end, n) in &summary.losses {
+        println!("{:<24}{:>10}", format!("loss: {}", game_end), n);
+    }
+    println!("{:<24}{:>10.2}", "avg outbreaks", summary.avg_outbreaks);
+    println!("{:<24}{:>10.2}", "avg turns", summary.avg_turns);
+    println!();
+    println!("{:<10}{:>8}{:>18}", "disease", "cured", "avg turns to cure");
+    for (disease, cure) in &summary.cures {
+        println!(
+            "{:<10}{:>8}{:>18}",
+            disease,
+            cure.cured,
+            format_option(cure.avg_turns_to_cure)
+        );
+    }
+}
+
+// one header and one row, so runs can be appended to the same file and compared
+fn print_csv(summary: &Summary) {
+    let mut header = vec![
+        "agent",
+        "difficulty",
+        "players",
+        "epidemics",
+        "ruleset",
+        "games",
+        "wins",
+        "win_rate",
+    ]
+    .into_iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<String>>();
+    let ruleset = &summary.ruleset;
+    let mut row = vec![
+        summary.agent.clone(),
+        format!("{:?}", ruleset.difficulty),
+        ruleset.nplayers.to_string(),
+        ruleset.nepidemics.to_string(),
+        // quoted, the description has commas
+        format!("\"{}\"", ruleset),
+        summary.games.to_string(),
+        summary.wins.to_string(),
+        format!("{:.4}", summary.win_rate),
+    ];
+    for (game_end, n) in &summary.losses {
+        header.push(format!("loss_{}", game_end));
+        row.push(n.to_string());
+    }
+    header.push("avg_outbreaks".to_string());
+    row.push(format!("{:.4}", summary.avg_outbreaks));
+    header.push("avg_turns".to_string());
+    row.push(format!("{:.4}", summary.avg_turns));
+    for (disease, cure) in &summary.cures {
+        header.push(format!("cured_{}", disease));
+        row.push(cure.cured.to_string());
+        header.push(format!("avg_turns_to_cure_{}", disease));
+        row.push(match cure.avg_turns_to_cure {
+            Some(turns) => format!("{:.4}", turns),
+            None => String::new(),
+        });
+    }
+    println!("{}", header.join(","));
+    println!("{}", row.join(","));
+}
+
+fn main() {
+    let args = match parse_args() {
+        Result::Ok(args) => args,
+        Result::Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let config = config_builder(&args).build().unwrap();
+    let start = Instant::now();
+    let results: Vec<GameResult> = (0..args.games)
+        .map(|i| play_game(&args, args.seed.wrapping_add(i)))
+        .collect();
+    let summary = summarize(&args, &config, &results, start.elapsed().as_secs_f64());
+    match args.format {
+        Format::Table => print_table(&summary),
+        Format::Csv => print_csv(&summary),
+        Format::Json => {
+            let report = serde_json::json!({ "summary": summary, "games": results });
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+    }
+}
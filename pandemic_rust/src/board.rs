@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::city_graph;
+use crate::game_enums::Disease;
+use crate::map_validation::{
+    diseases_of_cities, validate_map, MapCitySpec, MapSpec, DEFAULT_DISEASE_CUBES,
+};
+
+// A city on the board, by its index in the Board's list of cities. The bundled map's cities are
+// associated consts, e.g. CityCard::Atlanta
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize,
+)]
+pub struct CityCard(pub u16);
+
+impl CityCard {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn from_index(i: usize) -> CityCard {
+        CityCard(i as u16)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct City {
+    pub name: String,
+    pub disease: Disease,
+    // in the order outbreaks spread to them
+    pub neighbors: Vec<CityCard>,
+    pub coordinates: Option<(f64, f64)>,
+}
+
+// The map a game is played on. Serialized as its MapFile, so a saved board is validated like a
+// loaded one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "MapFile", try_from = "MapFile")]
+pub struct Board {
+    // what results on this map are recorded under
+    pub name: String,
+    cities: Vec<City>,
+    // as the map file spells them, which is what it's saved with
+    diseases: Vec<String>,
+    // capitalized, for display
+    disease_names: Vec<String>,
+    // cubes in the supply of each disease
+    disease_cubes: Vec<u32>,
+    starting_city: CityCard,
+}
+
+// A map as stored on disk, with cities referred to by name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapFile {
+    pub name: String,
+    pub starting_city: String,
+    // if left out, the colors the cities use, with the default supply of cubes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diseases: Vec<MapDisease>,
+    pub cities: Vec<MapCity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapDisease {
+    pub name: String,
+    #[serde(default = "default_disease_cubes")]
+    pub cubes: u32,
+}
+
+fn default_disease_cubes() -> u32 {
+    DEFAULT_DISEASE_CUBES
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapCity {
+    pub name: String,
+    pub disease: String,
+    pub neighbors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<(f64, f64)>,
+}
+
+// Everything wrong with a map file, see map_validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapError {
+    pub problems: Vec<String>,
+}
+
+impl MapError {
+    fn new(problem: String) -> Self {
+        Self {
+            problems: vec![problem],
+        }
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid map: {}", self.problems.join("; "))
+    }
+}
+
+impl std::error::Error for MapError {}
+
+pub const STANDARD_MAP: &str = "standard";
+
+// disease names are shown capitalized, whatever case the map file uses
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl From<Board> for MapFile {
+    fn from(board: Board) -> MapFile {
+        board.to_map()
+    }
+}
+
+impl TryFrom<MapFile> for Board {
+    type Error = MapError;
+
+    fn try_from(map: MapFile) -> Result<Board, MapError> {
+        Board::from_map(&map)
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::standard()
+    }
+}
+
+impl Board {
+    // the map built in by build.rs, the board game's 48 cities unless PANDEMIC_MAP names another
+    pub fn standard() -> Board {
+        let cities = (0..city_graph::NCITIES)
+            .map(|i| City {
+                name: city_graph::CITY_NAMES[i].to_string(),
+                disease: city_graph::CITY_DISEASES[i],
+                neighbors: city_graph::CITY_NEIGHBORS[i].to_vec(),
+                coordinates: None,
+            })
+            .collect();
+        Board {
+            name: city_graph::MAP_NAME.to_string(),
+            cities,
+            diseases: city_graph::DISEASE_NAMES
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            disease_names: city_graph::DISEASE_NAMES
+                .iter()
+                .map(|d| capitalize(d))
+                .collect(),
+            disease_cubes: city_graph::DISEASE_CUBES.to_vec(),
+            starting_city: city_graph::STARTING_CITY,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Board, MapError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|e| MapError::new(format!("can't read {}: {}", path.display(), e)))?;
+        Board::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Board, MapError> {
+        let map: MapFile = serde_json::from_str(json)
+            .map_err(|e| MapError::new(format!("not a map file: {}", e)))?;
+        Board::from_map(&map)
+    }
+
+    pub fn from_map(map: &MapFile) -> Result<Board, MapError> {
+        let diseases: Vec<MapDisease> = if map.diseases.is_empty() {
+            diseases_of_cities(map.cities.iter().map(|c| c.disease.as_str()))
+                .into_iter()
+                .map(|name| MapDisease {
+                    name: name.to_string(),
+                    cubes: DEFAULT_DISEASE_CUBES,
+                })
+                .collect()
+        } else {
+            map.diseases.clone()
+        };
+        let spec = MapSpec {
+            starting_city: &map.starting_city,
+            cities: map
+                .cities
+                .iter()
+                .map(|city| MapCitySpec {
+                    name: &city.name,
+                    disease: Some(&city.disease),
+                    neighbors: city.neighbors.iter().map(|n| n.as_str()).collect(),
+                })
+                .collect(),
+            diseases: diseases.iter().map(|d| d.name.as_str()).collect(),
+        };
+        let mut problems = validate_map(&spec);
+        if map.cities.len() > u16::MAX as usize {
+            problems.push(format!(
+                "{} cities, at most {} supported",
+                map.cities.len(),
+                u16::MAX
+            ));
+        }
+        if diseases.len() > u8::MAX as usize {
+            problems.push(format!(
+                "{} diseases, at most {} supported",
+                diseases.len(),
+                u8::MAX
+            ));
+        }
+        if !problems.is_empty() {
+            return Result::Err(MapError { problems });
+        }
+
+        let ids: HashMap<&str, CityCard> = map
+            .cities
+            .iter()
+            .enumerate()
+            .map(|(i, city)| (city.name.as_str(), CityCard::from_index(i)))
+            .collect();
+        let find_disease = |name: &str| {
+            diseases
+                .iter()
+                .position(|d| d.name.eq_ignore_ascii_case(name))
+                .map(Disease::from_index)
+        };
+        let cities = map
+            .cities
+            .iter()
+            .map(|city| City {
+                name: city.name.clone(),
+                disease: find_disease(&city.disease).unwrap(),
+                neighbors: city.neighbors.iter().map(|n| ids[n.as_str()]).collect(),
+                coordinates: city.coordinates,
+            })
+            .collect();
+        Result::Ok(Board {
+            name: map.name.clone(),
+            cities,
+            diseases: diseases.iter().map(|d| d.name.clone()).collect(),
+            disease_names: diseases.iter().map(|d| capitalize(&d.name)).collect(),
+            disease_cubes: diseases.iter().map(|d| d.cubes).collect(),
+            starting_city: ids[map.starting_city.as_str()],
+        })
+    }
+
+    pub fn to_map(&self) -> MapFile {
+        MapFile {
+            name: self.name.clone(),
+            starting_city: self.name(self.starting_city).to_string(),
+            diseases: self
+                .diseases()
+                .map(|d| MapDisease {
+                    name: self.diseases[d.index()].clone(),
+                    cubes: self.disease_cubes(d),
+                })
+                .collect(),
+            cities: self
+                .cities
+                .iter()
+                .map(|city| MapCity {
+                    name: city.name.clone(),
+                    disease: self.diseases[city.disease.index()].clone(),
+                    neighbors: city
+                        .neighbors
+                        .iter()
+                        .map(|n| self.name(*n).to_string())
+                        .collect(),
+                    coordinates: city.coordinates,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_map()).unwrap()
+    }
+
+    pub fn ncities(&self) -> usize {
+        self.cities.len()
+    }
+
+    pub fn cities(&self) -> impl Iterator<Item = CityCard> {
+        (0..self.cities.len()).map(CityCard::from_index)
+    }
+
+    pub fn contains(&self, city: CityCard) -> bool {
+        city.index() < self.cities.len()
+    }
+
+    pub fn city(&self, city: CityCard) -> &City {
+        &self.cities[city.index()]
+    }
+
+    pub fn neighbors(&self, city: CityCard) -> &[CityCard] {
+        &self.cities[city.index()].neighbors
+    }
+
+    pub fn disease(&self, city: CityCard) -> Disease {
+        self.cities[city.index()].disease
+    }
+
+    pub fn name(&self, city: CityCard) -> &str {
+        &self.cities[city.index()].name
+    }
+
+    pub fn find(&self, name: &str) -> Option<CityCard> {
+        self.cities
+            .iter()
+            .position(|c| c.name == name)
+            .map(CityCard::from_index)
+    }
+
+    pub fn starting_city(&self) -> CityCard {
+        self.starting_city
+    }
+
+    pub fn ndiseases(&self) -> usize {
+        self.diseases.len()
+    }
+
+    pub fn diseases(&self) -> impl Iterator<Item = Disease> {
+        (0..self.diseases.len()).map(Disease::from_index)
+    }
+
+    pub fn has_disease(&self, disease: Disease) -> bool {
+        disease.index() < self.diseases.len()
+    }
+
+    pub fn disease_name(&self, disease: Disease) -> &str {
+        &self.disease_names[disease.index()]
+    }
+
+    pub fn disease_cubes(&self, disease: Disease) -> u32 {
+        self.disease_cubes[disease.index()]
+    }
+
+    pub fn find_disease(&self, name: &str) -> Option<Disease> {
+        self.diseases
+            .iter()
+            .position(|d| d.eq_ignore_ascii_case(name))
+            .map(Disease::from_index)
+    }
+
+    // value for display, with its cities and diseases shown by name
+    pub fn named<'a, T: NamedOnBoard + ?Sized>(&'a self, value: &'a T) -> Named<'a, T> {
+        Named { board: self, value }
+    }
+}
+
+// Anything holding cities or diseases, which only the Board can put names to. Formatted like its
+// Debug output with names in place of the ids
+pub trait NamedOnBoard {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+pub struct Named<'a, T: ?Sized> {
+    board: &'a Board,
+    value: &'a T,
+}
+
+impl<T: NamedOnBoard + ?Sized> fmt::Display for Named<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt_named(self.board, f)
+    }
+}
+
+// ids off the board have no name, and are shown as they are
+impl NamedOnBoard for CityCard {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        if board.contains(*self) {
+            write!(f, "{}", board.name(*self))
+        } else {
+            write!(f, "{:?}", self)
+        }
+    }
+}
+
+impl NamedOnBoard for Disease {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        if board.has_disease(*self) {
+            write!(f, "{}", board.disease_name(*self))
+        } else {
+            write!(f, "{:?}", self)
+        }
+    }
+}
+
+impl<T: NamedOnBoard> NamedOnBoard for Option<T> {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Some(value) => write!(f, "Some({})", board.named(value)),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl<T: NamedOnBoard> NamedOnBoard for [T] {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", board.named(value))?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T: NamedOnBoard> NamedOnBoard for Vec<T> {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt_named(board, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+
+    #[test]
+    fn named_shows_cities_and_diseases_by_name() {
+        let board = Board::standard();
+        let atlanta = board.find("Atlanta").unwrap();
+        let chicago = board.find("Chicago").unwrap();
+        let blue = board.disease(atlanta);
+        let action = Action::DiscoverCure {
+            disease: blue,
+            cards: vec![atlanta, chicago],
+        };
+        assert_eq!(
+            board.named(&action).to_string(),
+            format!(
+                "DiscoverCure {{ disease: {}, cards: [Atlanta, Chicago] }}",
+                board.disease_name(blue)
+            )
+        );
+        // ids that aren't on the board keep their Debug form
+        let action = Action::BuildResearchStation {
+            relocate_from: Some(CityCard(5000)),
+        };
+        assert_eq!(
+            board.named(&action).to_string(),
+            "BuildResearchStation { relocate_from: Some(CityCard(5000)) }"
+        );
+        assert_eq!(
+            board.named(&Action::TreatDisease(Disease(9))).to_string(),
+            "TreatDisease(Disease(9))"
+        );
+    }
+
+    #[test]
+    fn disease_names_survive_a_round_trip() {
+        let mut map = Board::standard().to_map();
+        let renamed = |name: &str| format!("my{}", capitalize(name));
+        for disease in map.diseases.iter_mut() {
+            disease.name = renamed(&disease.name);
+        }
+        for city in map.cities.iter_mut() {
+            city.disease = renamed(&city.disease);
+        }
+        let board = Board::from_map(&map).unwrap();
+        assert_eq!(board.to_map(), map);
+        let disease = board.disease(board.starting_city());
+        let name = &map.diseases[disease.index()].name;
+        assert!(name.starts_with("my"));
+        assert_eq!(board.disease_name(disease), capitalize(name));
+        assert_eq!(board.find_disease(name), Some(disease));
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+    }
+}
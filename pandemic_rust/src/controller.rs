@@ -0,0 +1,272 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::action::Action;
+use crate::agent::ActionError;
+use crate::board::CityCard;
+use crate::game_enums::{EventCard, GameEnd, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+use crate::scheduler::{Decision, EventWindow, PendingDecision};
+
+// A policy for one or more players. The game asks the controller whenever it needs a choice, so
+// rules never block on I/O and bots, scripts and humans are interchangeable.
+// Events are chosen as a Decision::Action of a fully specified Action::PlayEvent, or as a
+// Decision::PlayEvent of Forecast or Resilient Population, which asks for the rest next with
+// choose_forecast_order or choose_resilient_population_target
+pub trait Controller {
+    // a turn action for agent_idx, who is the current player
+    fn choose_action(&mut self, state: &PandemicGameState, agent_idx: usize) -> Action;
+
+    // Asked before choose_discards when agent_idx, who is over the hand limit, holds event cards
+    // that could be played instead. Returning an event decision for one of them plays it, and
+    // choose_discards is asked again if the hand is still too big
+    fn choose_hand_limit_event(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        _ndiscards: usize,
+    ) -> Option<Decision> {
+        None
+    }
+
+    // ndiscards cards from agent_idx's hand, which is over the hand limit
+    fn choose_discards(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        ndiscards: usize,
+    ) -> Vec<PlayerCard>;
+
+    // an event decision by any of agent_idxs, or None if everyone passes. Asked once per window
+    // and again after each event played in it
+    fn choose_event(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idxs: &[usize],
+        window: EventWindow,
+    ) -> Option<Decision>;
+
+    // new order of the forecasted cards as indices into cards, both top of the deck first
+    fn choose_forecast_order(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        cards: &[CityCard],
+    ) -> Vec<usize>;
+
+    fn choose_resilient_population_target(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        cards: &[CityCard],
+    ) -> CityCard;
+
+    // Asked before choose_action when the Contingency Planner could store one of events. Returning
+    // an event spends an action on Action::ContingencyPlan
+    fn choose_contingency_plan(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        _events: &[EventCard],
+    ) -> Option<EventCard> {
+        None
+    }
+}
+
+fn storable_events(state: &PandemicGameState, agent_idx: usize) -> Vec<EventCard> {
+    state
+        .gen_contingency_plan_actions(agent_idx)
+        .into_iter()
+        .filter_map(|action| match action {
+            Action::ContingencyPlan(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+// The controller's answer to state.next_decision(), or None once the game is over
+pub fn decide<C: Controller + ?Sized>(
+    controller: &mut C,
+    state: &PandemicGameState,
+) -> Option<Decision> {
+    let decision = match state.next_decision() {
+        PendingDecision::GameOver(_) => return None,
+        PendingDecision::ChooseAction { agent_idx, .. } => {
+            let events = storable_events(state, agent_idx);
+            let stored = if events.is_empty() {
+                None
+            } else {
+                controller.choose_contingency_plan(state, agent_idx, &events)
+            };
+            match stored {
+                Some(event) => Decision::Action(Action::ContingencyPlan(event)),
+                None => Decision::Action(controller.choose_action(state, agent_idx)),
+            }
+        }
+        PendingDecision::ChooseDiscards {
+            agent_idx,
+            ndiscards,
+        } => {
+            let event = if state.hand_limit_events(agent_idx).is_empty() {
+                None
+            } else {
+                controller.choose_hand_limit_event(state, agent_idx, ndiscards)
+            };
+            match event {
+                Some(decision) => decision,
+                None => Decision::Discard(controller.choose_discards(state, agent_idx, ndiscards)),
+            }
+        }
+        PendingDecision::ChooseEvent { agent_idxs, window } => controller
+            .choose_event(state, &agent_idxs, window)
+            .unwrap_or(Decision::Pass),
+        PendingDecision::ChooseForecastOrder { agent_idx, cards } => {
+            Decision::ForecastOrder(controller.choose_forecast_order(state, agent_idx, &cards))
+        }
+        PendingDecision::ChooseResilientPopulationTarget { agent_idx, cards } => {
+            Decision::ResilientPopulationTarget(
+                controller.choose_resilient_population_target(state, agent_idx, &cards),
+            )
+        }
+    };
+    Some(decision)
+}
+
+// Play until the game ends. Fails on the first decision the game rejects
+pub fn play_game<C: Controller + ?Sized>(
+    controller: &mut C,
+    state: &mut PandemicGameState,
+) -> Result<GameEnd, ActionError> {
+    loop {
+        match decide(controller, state) {
+            Some(decision) => state.resolve(decision)?,
+            None => {
+                if let PendingDecision::GameOver(game_end) = state.next_decision() {
+                    return Result::Ok(game_end);
+                }
+            }
+        }
+    }
+}
+
+// Picks uniformly among legal choices. Events are only played in event windows and over the hand
+// limit, where passing or discarding is as likely as playing any one card
+pub struct RandomController {
+    rng: ChaCha8Rng,
+}
+
+impl RandomController {
+    pub fn new(seed: u64) -> RandomController {
+        RandomController {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // one of cards, or None as often as any one of them. Forecast's order is asked for next
+    fn random_event(
+        &mut self,
+        state: &PandemicGameState,
+        cards: &[(usize, EventCard)],
+    ) -> Option<Decision> {
+        let i = self.rng.gen_range(0..=cards.len());
+        let (agent_idx, event) = *cards.get(i)?;
+        if event == EventCard::Forecast {
+            return Some(Decision::PlayEvent { agent_idx, event });
+        }
+        let actions: Vec<Action> = state
+            .gen_event_actions(agent_idx)
+            .into_iter()
+            .filter(|a| matches!(a, Action::PlayEvent { event: e, .. } if e.card() == event))
+            .collect();
+        actions.choose(&mut self.rng).cloned().map(Decision::Action)
+    }
+}
+
+impl Controller for RandomController {
+    fn choose_action(&mut self, state: &PandemicGameState, agent_idx: usize) -> Action {
+        let actions: Vec<Action> = state
+            .legal_actions(agent_idx)
+            .into_iter()
+            .filter(|a| a.uses_action())
+            .collect();
+        actions
+            .choose(&mut self.rng)
+            .expect("there is always a drive action")
+            .clone()
+    }
+
+    fn choose_discards(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        ndiscards: usize,
+    ) -> Vec<PlayerCard> {
+        state
+            .player_hand(agent_idx)
+            .choose_multiple(&mut self.rng, ndiscards)
+            .copied()
+            .collect()
+    }
+
+    fn choose_event(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idxs: &[usize],
+        _window: EventWindow,
+    ) -> Option<Decision> {
+        let mut cards: Vec<(usize, EventCard)> = Vec::new();
+        for agent_idx in agent_idxs {
+            for event in state.playable_events(*agent_idx) {
+                cards.push((*agent_idx, event));
+            }
+        }
+        self.random_event(state, &cards)
+    }
+
+    fn choose_hand_limit_event(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        _ndiscards: usize,
+    ) -> Option<Decision> {
+        let cards: Vec<(usize, EventCard)> = state
+            .hand_limit_events(agent_idx)
+            .into_iter()
+            .map(|event| (agent_idx, event))
+            .collect();
+        self.random_event(state, &cards)
+    }
+
+    fn choose_forecast_order(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..cards.len()).collect();
+        order.shuffle(&mut self.rng);
+        order
+    }
+
+    fn choose_resilient_population_target(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> CityCard {
+        *cards
+            .choose(&mut self.rng)
+            .expect("resilient population needs an infection discard")
+    }
+
+    fn choose_contingency_plan(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        events: &[EventCard],
+    ) -> Option<EventCard> {
+        let i = self.rng.gen_range(0..=events.len());
+        events.get(i).copied()
+    }
+}
@@ -1,8 +1,9 @@
-use crate::city_graph::CityCard;
+use crate::board::CityCard;
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 use strum_macros::EnumString;
 
-#[derive(Debug, EnumIter)]
+#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum GameEnd {
     PlayerDeckLimit,
     DiseaseCubeLimit,
@@ -10,15 +11,26 @@ pub enum GameEnd {
     Win,
 }
 
-#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
-pub enum Disease {
-    Blue,
-    Red,
-    Black,
-    Yellow,
+// A disease, by its index in the Board's list of diseases. Which diseases there are, and what
+// they're called, is up to the map
+#[derive(
+    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize,
+)]
+pub struct Disease(pub u8);
+
+impl Disease {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn from_index(i: usize) -> Disease {
+        Disease(i as u8)
+    }
 }
 
-#[derive(Debug, Default, EnumIter, EnumString, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(
+    Debug, Default, EnumIter, EnumString, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize,
+)]
 pub enum EventCard {
     #[default]
     GovernmentGrant,
@@ -28,7 +40,7 @@ pub enum EventCard {
     OneQuietNight,
 }
 
-#[derive(Debug, Default, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, Default, EnumIter, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum PlayerCard {
     CityCard(CityCard),
     EventCard(EventCard),
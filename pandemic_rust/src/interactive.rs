@@ -0,0 +1,801 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+
+use strum::IntoEnumIterator;
+
+use crate::action::{Action, EventAction, MoveAction};
+use crate::board::CityCard;
+use crate::controller::{decide, Controller};
+use crate::game_enums::{Disease, EventCard, GameEnd, PlayerCard};
+use crate::pandemic_game::{EpidemicReport, PandemicGameState};
+use crate::scheduler::{Decision, EventWindow, PendingDecision};
+
+pub const HELP: &str = "commands:
+  drive <city>                        drive or ferry to a neighboring city
+  fly <city>                          direct flight, discarding the city's card
+  charter <city>                      charter flight, discarding the current city's card
+  shuttle <city>                      shuttle flight between research stations
+  treat [disease]                     remove a cube, the disease can be left out if there is one
+  build [from <city>]                 build a research station in the current city, moving the
+                                      one in <city> if all of them are on the board
+  give [city] to <player>             share knowledge, the city defaults to the current one
+  take [city] from <player>
+  cure <disease> [city,city,...]      discover a cure, the cards can be left out if unambiguous
+  dispatch <player> <drive|fly|charter|shuttle|join> <city>
+  ops <city> discard <city>           operations expert move from a research station
+  plan <event>                        contingency planner takes an event from the discard pile
+  event airlift <player> <city>
+  event grant <city> [from <city>]
+  event resilient [city]              leave out the city to choose from the infection discard
+  event forecast [i,j,...]            leave out the order to see the cards first
+  event quiet
+  pass                                play no event now
+  discard <card,card,...>             get back down to the hand limit
+  order <i,j,...>                     new forecast order, indices into the cards shown, top first
+  target <city>                       resilient population target
+  show                                print the board
+  undo                                take back your last decision
+  save <file>                         save the game, pandemic-play --load <file> resumes it
+  help
+  quit
+players are named by role or index, and names can be abbreviated or slightly misspelled";
+
+// What a line of input asks for
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Decision(Decision),
+    Help,
+    Show,
+    Undo,
+    Save(String),
+    Quit,
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur.push(substitution.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// Matches input ignoring case, spaces and punctuation: exactly, then as the unique prefix or
+// substring of a name, then as a name with a single typo
+pub fn fuzzy_match<T: Copy>(
+    input: &str,
+    candidates: &[(String, T)],
+    what: &str,
+) -> Result<T, String> {
+    let key = normalize(input);
+    if key.is_empty() {
+        return Result::Err(format!("missing {}", what));
+    }
+    let names: Vec<(String, T)> = candidates
+        .iter()
+        .map(|(name, value)| (normalize(name), *value))
+        .collect();
+    if let Some((_, value)) = names.iter().find(|(name, _)| *name == key) {
+        return Result::Ok(*value);
+    }
+    let matching = |pred: &dyn Fn(&str) -> bool| -> Vec<usize> {
+        (0..names.len()).filter(|i| pred(&names[*i].0)).collect()
+    };
+    for found in [
+        matching(&|name| name.starts_with(&key)),
+        matching(&|name| name.contains(&key)),
+        matching(&|name| edit_distance(name, &key) <= 1),
+    ] {
+        match found.len() {
+            0 => continue,
+            1 => return Result::Ok(candidates[found[0]].1),
+            _ => {
+                let options: Vec<&str> = found.iter().map(|i| candidates[*i].0.as_str()).collect();
+                return Result::Err(format!(
+                    "ambiguous {} {}: {}",
+                    what,
+                    input,
+                    options.join(", ")
+                ));
+            }
+        }
+    }
+    let mut closest: Vec<(usize, &str)> = names
+        .iter()
+        .zip(candidates)
+        .map(|((name, _), (display, _))| (edit_distance(name, &key), display.as_str()))
+        .collect();
+    closest.sort();
+    let suggestions: Vec<&str> = closest.iter().take(3).map(|(_, name)| *name).collect();
+    Result::Err(format!(
+        "unknown {} {}, did you mean {}?",
+        what,
+        input,
+        suggestions.join(", ")
+    ))
+}
+
+fn named<T: Copy + std::fmt::Debug>(values: impl Iterator<Item = T>) -> Vec<(String, T)> {
+    values.map(|v| (format!("{:?}", v), v)).collect()
+}
+
+pub fn parse_city(state: &PandemicGameState, input: &str) -> Result<CityCard, String> {
+    let board = &state.config.board;
+    let candidates: Vec<(String, CityCard)> = board
+        .cities()
+        .map(|city| (board.name(city).to_string(), city))
+        .collect();
+    fuzzy_match(input, &candidates, "city")
+}
+
+pub fn parse_disease(state: &PandemicGameState, input: &str) -> Result<Disease, String> {
+    let board = &state.config.board;
+    let candidates: Vec<(String, Disease)> = board
+        .diseases()
+        .map(|disease| (board.disease_name(disease).to_string(), disease))
+        .collect();
+    fuzzy_match(input, &candidates, "disease")
+}
+
+pub fn parse_event_card(input: &str) -> Result<EventCard, String> {
+    fuzzy_match(input, &named(EventCard::iter()), "event")
+}
+
+pub fn parse_player(state: &PandemicGameState, input: &str) -> Result<usize, String> {
+    if let Result::Ok(agent_idx) = input.trim().parse::<usize>() {
+        if agent_idx < state.agents.len() {
+            return Result::Ok(agent_idx);
+        }
+        return Result::Err(format!("there is no player {}", agent_idx));
+    }
+    let candidates: Vec<(String, usize)> = (0..state.agents.len())
+        .map(|i| (format!("{:?}", state.agent_name(i)), i))
+        .collect();
+    fuzzy_match(input, &candidates, "player")
+}
+
+fn parse_hand_card(
+    state: &PandemicGameState,
+    agent_idx: usize,
+    input: &str,
+) -> Result<PlayerCard, String> {
+    let candidates: Vec<(String, PlayerCard)> = state
+        .player_hand(agent_idx)
+        .iter()
+        .map(|card| match card {
+            PlayerCard::CityCard(city) => (state.city_name(*city).to_string(), *card),
+            PlayerCard::EventCard(event) => (format!("{:?}", event), *card),
+            PlayerCard::Epidemic => ("Epidemic".to_string(), *card),
+        })
+        .collect();
+    fuzzy_match(input, &candidates, "card in hand")
+}
+
+fn parse_indices(input: &str) -> Result<Vec<usize>, String> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| format!("not an index: {}", s)))
+        .collect()
+}
+
+// splits "<left> <word> <right>" on the first standalone word
+fn split_on_word<'a>(input: &'a str, word: &str) -> Option<(&'a str, &'a str)> {
+    let padded = format!(" {} ", word);
+    if let Some(i) = input.find(&padded) {
+        return Some((&input[..i], &input[i + padded.len()..]));
+    }
+    input.strip_prefix(&padded[1..]).map(|right| ("", right))
+}
+
+fn parse_share_knowledge(
+    state: &PandemicGameState,
+    agent_idx: usize,
+    rest: &str,
+    giving: bool,
+) -> Result<Action, String> {
+    let word = if giving { "to" } else { "from" };
+    let (city, player) =
+        split_on_word(rest, word).ok_or(format!("expected <city> {} <player>", word))?;
+    let other_agent_idx = parse_player(state, player)?;
+    let city = if city.trim().is_empty() {
+        state.player_location(agent_idx)
+    } else {
+        parse_city(state, city)?
+    };
+    let (giving_agent_idx, receiving_agent_idx) = if giving {
+        (agent_idx, other_agent_idx)
+    } else {
+        (other_agent_idx, agent_idx)
+    };
+    Result::Ok(Action::ShareKnowledge {
+        giving_agent_idx,
+        receiving_agent_idx,
+        city,
+    })
+}
+
+fn parse_cure(state: &PandemicGameState, agent_idx: usize, rest: &str) -> Result<Action, String> {
+    let (disease, cards) = match rest.split_once(char::is_whitespace) {
+        Some((disease, cards)) => (disease, cards),
+        None => (rest, ""),
+    };
+    let disease = parse_disease(state, disease)?;
+    if !cards.trim().is_empty() {
+        let cards = cards
+            .split(',')
+            .map(|city| parse_city(state, city))
+            .collect::<Result<Vec<CityCard>, String>>()?;
+        return Result::Ok(Action::DiscoverCure { disease, cards });
+    }
+    let options: Vec<Action> = state
+        .gen_discover_cure_actions(agent_idx)
+        .into_iter()
+        .filter(|a| matches!(a, Action::DiscoverCure { disease: d, .. } if *d == disease))
+        .collect();
+    match options.len() {
+        0 => Result::Err(format!("can't cure {} now", state.disease_name(disease))),
+        1 => Result::Ok(options[0].clone()),
+        _ => Result::Err(format!(
+            "more than one way to cure {}, list the cards to discard",
+            state.disease_name(disease)
+        )),
+    }
+}
+
+fn parse_dispatch(state: &PandemicGameState, rest: &str) -> Result<Action, String> {
+    let mut words = rest.splitn(3, char::is_whitespace);
+    let player = words.next().unwrap_or("");
+    let kind = words.next().unwrap_or("");
+    let city = parse_city(state, words.next().unwrap_or(""))?;
+    let move_action = match kind {
+        "drive" => MoveAction::Drive(city),
+        "fly" => MoveAction::DirectFlight(city),
+        "charter" => MoveAction::CharterFlight(city),
+        "shuttle" => MoveAction::ShuttleFlight(city),
+        "join" => MoveAction::DispatchFlight(city),
+        other => {
+            return Result::Err(format!(
+                "unknown move {}, expected drive, fly, charter, shuttle or join",
+                other
+            ))
+        }
+    };
+    Result::Ok(Action::DispatchMove {
+        agent_idx: parse_player(state, player)?,
+        move_action,
+    })
+}
+
+// the agent among agent_idxs holding event, preferring the first listed
+fn event_holder(
+    state: &PandemicGameState,
+    agent_idxs: &[usize],
+    event: EventCard,
+) -> Result<usize, String> {
+    agent_idxs
+        .iter()
+        .copied()
+        .find(|i| state.playable_events(*i).contains(&event))
+        .ok_or(format!("nobody who can act now can play {:?}", event))
+}
+
+fn parse_event(
+    state: &PandemicGameState,
+    agent_idxs: &[usize],
+    rest: &str,
+) -> Result<Decision, String> {
+    let (name, args) = match rest.split_once(char::is_whitespace) {
+        Some((name, args)) => (name, args.trim()),
+        None => (rest, ""),
+    };
+    let card = parse_event_card(name)?;
+    let agent_idx = event_holder(state, agent_idxs, card)?;
+    let event = match card {
+        EventCard::Airlift => {
+            let (player, city) = args
+                .split_once(char::is_whitespace)
+                .ok_or("expected event airlift <player> <city>")?;
+            EventAction::Airlift {
+                agent_idx: parse_player(state, player)?,
+                city: parse_city(state, city)?,
+            }
+        }
+        EventCard::GovernmentGrant => match split_on_word(args, "from") {
+            Some((city, from_city)) => EventAction::GovernmentGrant {
+                city: parse_city(state, city)?,
+                relocate_from: Some(parse_city(state, from_city)?),
+            },
+            None => EventAction::GovernmentGrant {
+                city: parse_city(state, args)?,
+                relocate_from: None,
+            },
+        },
+        EventCard::ResilientPopulation if args.is_empty() => {
+            return Result::Ok(Decision::PlayEvent {
+                agent_idx,
+                event: card,
+            })
+        }
+        EventCard::ResilientPopulation => {
+            EventAction::ResilientPopulation(parse_city(state, args)?)
+        }
+        EventCard::Forecast if args.is_empty() => {
+            return Result::Ok(Decision::PlayEvent {
+                agent_idx,
+                event: card,
+            })
+        }
+        EventCard::Forecast => EventAction::Forecast(parse_indices(args)?),
+        EventCard::OneQuietNight if args.is_empty() => EventAction::OneQuietNight,
+        EventCard::OneQuietNight => return Result::Err("expected event quiet".to_string()),
+    };
+    Result::Ok(Decision::Action(Action::PlayEvent { agent_idx, event }))
+}
+
+// Parse a line typed while the game waits on pending. human_idxs are the players at the terminal,
+// who may play events when pending lets them
+pub fn parse_command(
+    state: &PandemicGameState,
+    pending: &PendingDecision,
+    human_idxs: &[usize],
+    input: &str,
+) -> Result<Command, String> {
+    let input = input.trim();
+    let (verb, rest) = match input.split_once(char::is_whitespace) {
+        Some((verb, rest)) => (verb.to_lowercase(), rest.trim()),
+        None => (input.to_lowercase(), ""),
+    };
+    // the player whose decision this is, for commands about "my" pawn or hand
+    let agent_idx = match pending {
+        PendingDecision::ChooseAction { agent_idx, .. }
+        | PendingDecision::ChooseDiscards { agent_idx, .. }
+        | PendingDecision::ChooseForecastOrder { agent_idx, .. }
+        | PendingDecision::ChooseResilientPopulationTarget { agent_idx, .. } => *agent_idx,
+        PendingDecision::ChooseEvent { .. } | PendingDecision::GameOver(_) => {
+            state.current_player_i as usize
+        }
+    };
+    let takes_no_args = matches!(
+        verb.as_str(),
+        "help" | "?" | "show" | "board" | "undo" | "quit" | "exit" | "pass"
+    );
+    if takes_no_args && !rest.is_empty() {
+        return Result::Err(format!("{} takes no arguments", verb));
+    }
+    let action = |action: Action| Result::Ok(Command::Decision(Decision::Action(action)));
+    match verb.as_str() {
+        "" => Result::Err("type a command, or help".to_string()),
+        "help" | "?" => Result::Ok(Command::Help),
+        "show" | "board" => Result::Ok(Command::Show),
+        "undo" => Result::Ok(Command::Undo),
+        "save" if rest.is_empty() => Result::Err("save to which file?".to_string()),
+        "save" => Result::Ok(Command::Save(rest.to_string())),
+        "quit" | "exit" => Result::Ok(Command::Quit),
+        "drive" => action(Action::Drive(parse_city(state, rest)?)),
+        "fly" => action(Action::DirectFlight(parse_city(state, rest)?)),
+        "charter" => action(Action::CharterFlight(parse_city(state, rest)?)),
+        "shuttle" => action(Action::ShuttleFlight(parse_city(state, rest)?)),
+        "treat" if rest.is_empty() => {
+            let city = state.player_location(agent_idx);
+            let diseases: Vec<Disease> = state
+                .config
+                .board
+                .diseases()
+                .filter(|d| state.get_n_disease_cubes(city, *d) > 0)
+                .collect();
+            match diseases[..] {
+                [disease] => action(Action::TreatDisease(disease)),
+                [] => Result::Err(format!("no disease cubes in {}", state.city_name(city))),
+                _ => Result::Err(format!(
+                    "which disease? {}",
+                    diseases
+                        .iter()
+                        .map(|d| state.disease_name(*d))
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )),
+            }
+        }
+        "treat" => action(Action::TreatDisease(parse_disease(state, rest)?)),
+        "build" => match split_on_word(rest, "from") {
+            Some(("", from_city)) => action(Action::BuildResearchStation {
+                relocate_from: Some(parse_city(state, from_city)?),
+            }),
+            None if rest.is_empty() => action(Action::BuildResearchStation {
+                relocate_from: None,
+            }),
+            _ => Result::Err("expected build [from <city>]".to_string()),
+        },
+        "give" => action(parse_share_knowledge(state, agent_idx, rest, true)?),
+        "take" => action(parse_share_knowledge(state, agent_idx, rest, false)?),
+        "cure" => action(parse_cure(state, agent_idx, rest)?),
+        "dispatch" => action(parse_dispatch(state, rest)?),
+        "ops" => {
+            let (city, discard) =
+                split_on_word(rest, "discard").ok_or("expected ops <city> discard <city>")?;
+            action(Action::OperationsMove {
+                city: parse_city(state, city)?,
+                discard: parse_city(state, discard)?,
+            })
+        }
+        "plan" => action(Action::ContingencyPlan(parse_event_card(rest)?)),
+        "event" => {
+            // anyone can play an event between actions, and a window only asks some players
+            let mut agent_idxs: Vec<usize> = match pending {
+                PendingDecision::ChooseAction { .. } => human_idxs.to_vec(),
+                PendingDecision::ChooseEvent { agent_idxs, .. } => agent_idxs
+                    .iter()
+                    .copied()
+                    .filter(|i| human_idxs.contains(i))
+                    .collect(),
+                PendingDecision::ChooseDiscards { agent_idx, .. } => vec![*agent_idx],
+                _ => vec![],
+            };
+            agent_idxs.sort_by_key(|i| *i != agent_idx);
+            Result::Ok(Command::Decision(parse_event(state, &agent_idxs, rest)?))
+        }
+        "pass" => Result::Ok(Command::Decision(Decision::Pass)),
+        "discard" => {
+            let cards = rest
+                .split(',')
+                .map(|card| parse_hand_card(state, agent_idx, card))
+                .collect::<Result<Vec<PlayerCard>, String>>()?;
+            Result::Ok(Command::Decision(Decision::Discard(cards)))
+        }
+        "order" => Result::Ok(Command::Decision(Decision::ForecastOrder(parse_indices(
+            rest,
+        )?))),
+        "target" => Result::Ok(Command::Decision(Decision::ResilientPopulationTarget(
+            parse_city(state, rest)?,
+        ))),
+        other => Result::Err(format!("unknown command {}, try help", other)),
+    }
+}
+
+fn card_name(state: &PandemicGameState, card: &PlayerCard) -> String {
+    state.config.board.named(card).to_string()
+}
+
+// A summary of the board for players
+pub fn describe(state: &PandemicGameState) -> String {
+    let mut out = String::new();
+    let rate = state.infection_rate();
+    let _ = writeln!(
+        out,
+        "outbreaks {}/{}, infection rate {}, player deck {} cards",
+        state.outbreaks,
+        state.config.max_outbreaks,
+        rate,
+        state.player_deck.len()
+    );
+    for disease in state.config.board.diseases() {
+        let status = if state.is_eradicated(disease) {
+            "eradicated"
+        } else if state.is_cured(disease) {
+            "cured"
+        } else {
+            "not cured"
+        };
+        let _ = writeln!(
+            out,
+            "  {:<7} {:>2} cubes on the board, {}",
+            state.disease_name(disease),
+            state.total_cubes_on_board_per_disease[disease.index()],
+            status
+        );
+    }
+    let stations: Vec<String> = state
+        .research_stations
+        .iter()
+        .map(|c| state.city_name(*c).to_string())
+        .collect();
+    let _ = writeln!(
+        out,
+        "research stations ({} of {}): {}",
+        stations.len(),
+        state.config.max_research_stations,
+        stations.join(", ")
+    );
+    let mut cities: Vec<CityCard> = state
+        .config
+        .board
+        .cities()
+        .filter(|c| {
+            state
+                .config
+                .board
+                .diseases()
+                .any(|d| state.get_n_disease_cubes(*c, d) > 0)
+        })
+        .collect();
+    cities.sort_by_key(|c| {
+        std::cmp::Reverse(
+            state
+                .config
+                .board
+                .diseases()
+                .map(|d| state.get_n_disease_cubes(*c, d))
+                .max(),
+        )
+    });
+    let _ = writeln!(out, "infected cities:");
+    for city in cities {
+        let cubes: Vec<String> = state
+            .config
+            .board
+            .diseases()
+            .filter(|d| state.get_n_disease_cubes(city, *d) > 0)
+            .map(|d| format!("{} {}", state.get_n_disease_cubes(city, d), state.disease_name(d)))
+            .collect();
+        let _ = writeln!(out, "  {:<16} {}", state.city_name(city), cubes.join(", "));
+    }
+    let discard: Vec<String> = state
+        .infection_discard
+        .iter()
+        .map(|c| state.city_name(*c).to_string())
+        .collect();
+    let _ = writeln!(out, "infection discard: {}", discard.join(", "));
+    for agent_idx in 0..state.agents.len() {
+        let hand: Vec<String> = state
+            .player_hand(agent_idx)
+            .iter()
+            .map(|card| card_name(state, card))
+            .collect();
+        let marker = if agent_idx == state.current_player_i as usize {
+            "*"
+        } else {
+            " "
+        };
+        let _ = writeln!(
+            out,
+            "{}{} {:?} in {}: {}",
+            marker,
+            agent_idx,
+            state.agent_name(agent_idx),
+            state.city_name(state.player_location(agent_idx)),
+            hand.join(", ")
+        );
+    }
+    if let Some(event) = state.contingency_planner_event_card {
+        let _ = writeln!(out, "stored by the contingency planner: {:?}", event);
+    }
+    out
+}
+
+fn describe_epidemic(state: &PandemicGameState, report: &EpidemicReport) -> String {
+    format!(
+        "epidemic in {}: {} {} cube(s) placed, {} outbreak(s), infection rate now {}",
+        state.city_name(report.city),
+        report.cubes_added,
+        state.disease_name(report.disease),
+        report.infection.outbreaks.len(),
+        report.infection_rate
+    )
+}
+
+fn window_name(window: EventWindow) -> String {
+    match window {
+        EventWindow::BeforeAction { actions_left } => {
+            format!("before an action, {} left this turn", actions_left)
+        }
+        EventWindow::BeforeDraw { cards_left } => {
+            format!("before drawing, {} player card(s) left", cards_left)
+        }
+        EventWindow::EpidemicIntensify => "before the epidemic intensifies".to_string(),
+        EventWindow::BeforeInfect => "before infecting cities".to_string(),
+    }
+}
+
+fn prompt(state: &PandemicGameState, pending: &PendingDecision) -> String {
+    match pending {
+        PendingDecision::ChooseAction {
+            agent_idx,
+            actions_left,
+        } => format!(
+            "{} {:?} in {}, {} action(s) left> ",
+            agent_idx,
+            state.agent_name(*agent_idx),
+            state.city_name(state.player_location(*agent_idx)),
+            actions_left
+        ),
+        PendingDecision::ChooseDiscards {
+            agent_idx,
+            ndiscards,
+        } => {
+            let hand: Vec<String> = state
+                .player_hand(*agent_idx)
+                .iter()
+                .map(|card| card_name(state, card))
+                .collect();
+            let or_event = if state.hand_limit_events(*agent_idx).is_empty() {
+                ""
+            } else {
+                " or play events from their hand"
+            };
+            format!(
+                "{:?} is over the hand limit and must discard {}{}: {}\ndiscard> ",
+                state.agent_name(*agent_idx),
+                ndiscards,
+                or_event,
+                hand.join(", ")
+            )
+        }
+        PendingDecision::ChooseEvent { agent_idxs, window } => {
+            let players: Vec<String> = agent_idxs
+                .iter()
+                .map(|i| format!("{} {:?}", i, state.agent_name(*i)))
+                .collect();
+            format!(
+                "{}, {} may play an event or pass> ",
+                window_name(*window),
+                players.join(", ")
+            )
+        }
+        PendingDecision::ChooseForecastOrder { cards, .. } => {
+            let cards: Vec<String> = cards
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("{} {}", i, state.city_name(*c)))
+                .collect();
+            format!(
+                "forecast, top of the infection deck first: {}\norder> ",
+                cards.join(", ")
+            )
+        }
+        PendingDecision::ChooseResilientPopulationTarget { cards, .. } => {
+            let cards: Vec<String> = cards
+                .iter()
+                .map(|c| state.city_name(*c).to_string())
+                .collect();
+            format!(
+                "remove from the game one of: {}\ntarget> ",
+                cards.join(", ")
+            )
+        }
+        PendingDecision::GameOver(game_end) => format!("game over: {:?}", game_end),
+    }
+}
+
+// Runs a game at the terminal. Seats without a bot are played by whoever is typing, and every
+// decision they make can be undone
+pub struct TerminalClient {
+    pub state: PandemicGameState,
+    bots: Vec<Option<Box<dyn Controller>>>,
+    // states before each decision typed at the terminal, for undo
+    history: Vec<PandemicGameState>,
+}
+
+impl TerminalClient {
+    // bots[agent_idx] plays that seat, None for humans
+    pub fn new(state: PandemicGameState, bots: Vec<Option<Box<dyn Controller>>>) -> TerminalClient {
+        assert_eq!(bots.len(), state.agents.len());
+        TerminalClient {
+            state,
+            bots,
+            history: Vec::new(),
+        }
+    }
+
+    fn human_idxs(&self) -> Vec<usize> {
+        (0..self.bots.len())
+            .filter(|i| self.bots[*i].is_none())
+            .collect()
+    }
+
+    // A bot's answer to pending, or None if a human has to answer
+    fn bot_decision(&mut self, pending: &PendingDecision) -> Option<Decision> {
+        let agent_idx = match pending {
+            PendingDecision::ChooseAction { agent_idx, .. }
+            | PendingDecision::ChooseDiscards { agent_idx, .. }
+            | PendingDecision::ChooseForecastOrder { agent_idx, .. }
+            | PendingDecision::ChooseResilientPopulationTarget { agent_idx, .. } => *agent_idx,
+            PendingDecision::ChooseEvent { agent_idxs, window } => {
+                for agent_idx in agent_idxs {
+                    if let Some(bot) = self.bots[*agent_idx].as_mut() {
+                        if let Some(decision) =
+                            bot.choose_event(&self.state, &[*agent_idx], *window)
+                        {
+                            return Some(decision);
+                        }
+                    }
+                }
+                if agent_idxs.iter().any(|i| self.bots[*i].is_none()) {
+                    return None;
+                }
+                return Some(Decision::Pass);
+            }
+            PendingDecision::GameOver(_) => return None,
+        };
+        let bot = self.bots[agent_idx].as_mut()?;
+        decide(bot.as_mut(), &self.state)
+    }
+
+    // Play until the game ends or input runs out or asks to quit
+    pub fn run<R: BufRead, W: Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<Option<GameEnd>> {
+        writeln!(output, "{}", describe(&self.state))?;
+        // bots are asked about events once per window, not again after every typo
+        let mut bots_passed = false;
+        let mut nepidemics_shown = self.state.epidemic_reports.len();
+        loop {
+            for report in &self.state.epidemic_reports[nepidemics_shown..] {
+                writeln!(output, "{}", describe_epidemic(&self.state, report))?;
+            }
+            nepidemics_shown = self.state.epidemic_reports.len();
+            let pending = self.state.next_decision();
+            if let PendingDecision::GameOver(game_end) = pending {
+                writeln!(output, "{}", describe(&self.state))?;
+                writeln!(output, "game over: {:?}", game_end)?;
+                return Result::Ok(Some(game_end));
+            }
+            if !bots_passed {
+                if let Some(decision) = self.bot_decision(&pending) {
+                    writeln!(output, "bot: {}", self.state.config.board.named(&decision))?;
+                    if let Result::Err(e) = self.state.resolve(decision) {
+                        panic!("bot made an invalid decision: {}", e);
+                    }
+                    continue;
+                }
+                bots_passed = true;
+            }
+
+            write!(output, "{}", prompt(&self.state, &pending))?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Result::Ok(None);
+            }
+            let command = match parse_command(&self.state, &pending, &self.human_idxs(), &line) {
+                Result::Ok(command) => command,
+                Result::Err(e) => {
+                    writeln!(output, "{}", e)?;
+                    continue;
+                }
+            };
+            match command {
+                Command::Help => writeln!(output, "{}", HELP)?,
+                Command::Show => writeln!(output, "{}", describe(&self.state))?,
+                Command::Quit => return Result::Ok(None),
+                Command::Save(path) => match self.state.save_snapshot(&path) {
+                    Result::Ok(()) => writeln!(output, "saved to {}", path)?,
+                    Result::Err(e) => writeln!(output, "{}", e)?,
+                },
+                Command::Undo => match self.history.pop() {
+                    Some(state) => {
+                        self.state = state;
+                        bots_passed = false;
+                        nepidemics_shown = nepidemics_shown.min(self.state.epidemic_reports.len());
+                        writeln!(output, "undone")?;
+                    }
+                    None => writeln!(output, "nothing to undo")?,
+                },
+                Command::Decision(decision) => {
+                    let before = self.state.clone();
+                    match self.state.resolve(decision) {
+                        Result::Ok(_) => {
+                            self.history.push(before);
+                            bots_passed = false;
+                        }
+                        Result::Err(e) => writeln!(output, "{}", e)?,
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use strum::IntoEnumIterator;
+
+use crate::game_enums::{EventCard, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+
+// where each card was found, for reporting duplicates
+fn count_cards<T: Copy + Eq + std::hash::Hash>(piles: &[(&str, &[T])]) -> HashMap<T, Vec<String>> {
+    let mut found: HashMap<T, Vec<String>> = HashMap::new();
+    for (name, pile) in piles {
+        for card in pile.iter() {
+            found.entry(*card).or_default().push(name.to_string());
+        }
+    }
+    found
+}
+
+impl PandemicGameState {
+    // Bookkeeping that should hold between any two steps of the game, as a list of everything
+    // that doesn't. Empty for a consistent state
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        // the other checks index by city and disease
+        self.check_shape_invariants(&mut violations);
+        if !violations.is_empty() {
+            return violations;
+        }
+        self.check_cube_invariants(&mut violations);
+        self.check_player_card_invariants(&mut violations);
+        self.check_infection_card_invariants(&mut violations);
+        self.check_board_invariants(&mut violations);
+        violations
+    }
+
+    fn check_shape_invariants(&self, violations: &mut Vec<String>) {
+        let board = &self.config.board;
+        if self.config.ndiseases as usize != board.ndiseases() {
+            violations.push(format!(
+                "config has {} diseases but the board has {}",
+                self.config.ndiseases,
+                board.ndiseases()
+            ));
+        }
+        let ncubes = board.ncities() * board.ndiseases();
+        if self.cur_city_diseases.len() != ncubes {
+            violations.push(format!(
+                "cubes kept for {} city and disease pairs, the board has {}",
+                self.cur_city_diseases.len(),
+                ncubes
+            ));
+        }
+        for (name, len) in [
+            ("cube totals", self.total_cubes_on_board_per_disease.len()),
+            ("cures", self.cured_diseases.len()),
+            ("cube supplies", self.config.max_disease_cubes.len()),
+        ] {
+            if len != board.ndiseases() {
+                violations.push(format!(
+                    "{} kept for {} diseases, the board has {}",
+                    name,
+                    len,
+                    board.ndiseases()
+                ));
+            }
+        }
+    }
+
+    fn check_cube_invariants(&self, violations: &mut Vec<String>) {
+        for disease in self.config.board.diseases() {
+            let mut sum = 0;
+            for city in self.config.board.cities() {
+                let ncubes = self.get_n_disease_cubes(city, disease);
+                if ncubes > 3 {
+                    violations.push(format!(
+                        "{} has {} {} cubes",
+                        self.city_name(city),
+                        ncubes,
+                        self.disease_name(disease)
+                    ));
+                }
+                sum += ncubes;
+            }
+            let total = self.total_cubes_on_board_per_disease[disease.index()];
+            if sum != total {
+                violations.push(format!(
+                    "{} cubes on the board add up to {} but the total is {}",
+                    self.disease_name(disease),
+                    sum,
+                    total
+                ));
+            }
+            let supply = self.config.max_disease_cubes[disease.index()];
+            if total > supply {
+                violations.push(format!(
+                    "{} {} cubes on the board, only {} exist",
+                    total,
+                    self.disease_name(disease),
+                    supply
+                ));
+            }
+        }
+    }
+
+    fn check_player_card_invariants(&self, violations: &mut Vec<String>) {
+        let hand_names: Vec<String> = (0..self.player_hands.len())
+            .map(|i| format!("hand {}", i))
+            .collect();
+        let stored: Vec<PlayerCard> = self
+            .contingency_planner_event_card
+            .map(PlayerCard::EventCard)
+            .into_iter()
+            .collect();
+        let mut piles: Vec<(&str, &[PlayerCard])> = vec![
+            ("player deck", &self.player_deck),
+            ("player discard", &self.player_discard),
+            ("contingency plan", &stored),
+        ];
+        for (name, hand) in hand_names.iter().zip(&self.player_hands) {
+            piles.push((name, hand));
+        }
+        let found = count_cards(&piles);
+
+        // every city card exactly once
+        for city in self.config.board.cities() {
+            match found.get(&PlayerCard::CityCard(city)) {
+                None => violations.push(format!("player card {} is missing", self.city_name(city))),
+                Some(places) if places.len() > 1 => violations.push(format!(
+                    "player card {} is in {}",
+                    self.city_name(city),
+                    places.join(", ")
+                )),
+                _ => {}
+            }
+        }
+        // events at most once, a played contingency plan is removed from the game
+        for event in EventCard::iter() {
+            match found.get(&PlayerCard::EventCard(event)) {
+                Some(_) if !self.config.events.contains(&event) => {
+                    violations.push(format!("{:?} is not in this game", event))
+                }
+                Some(places) if places.len() > 1 => {
+                    violations.push(format!("{:?} is in {}", event, places.join(", ")))
+                }
+                _ => {}
+            }
+        }
+        // epidemics are discarded once drawn, and never held
+        if let Some(places) = found.get(&PlayerCard::Epidemic) {
+            let held: Vec<&str> = places
+                .iter()
+                .map(|p| p.as_str())
+                .filter(|p| *p != "player deck" && *p != "player discard")
+                .collect();
+            if !held.is_empty() {
+                violations.push(format!("epidemic card in {}", held.join(", ")));
+            }
+            if places.len() > self.config.nepidemics as usize {
+                violations.push(format!(
+                    "{} epidemic cards, only {} in the game",
+                    places.len(),
+                    self.config.nepidemics
+                ));
+            }
+        }
+        for (name, pile) in &piles {
+            for card in pile.iter() {
+                if let PlayerCard::CityCard(city) = card {
+                    if !self.config.board.contains(*city) {
+                        violations
+                            .push(format!("{} has {:?}, which is not on the map", name, city));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_infection_card_invariants(&self, violations: &mut Vec<String>) {
+        let found = count_cards(&[
+            ("infection deck", &self.infection_deck),
+            ("infection discard", &self.infection_discard),
+            ("removed from the game", &self.infection_cards_removed),
+        ]);
+        for city in self.config.board.cities() {
+            match found.get(&city) {
+                None => violations.push(format!(
+                    "infection card {} is missing",
+                    self.city_name(city)
+                )),
+                Some(places) if places.len() > 1 => violations.push(format!(
+                    "infection card {} is in {}",
+                    self.city_name(city),
+                    places.join(", ")
+                )),
+                _ => {}
+            }
+        }
+        for city in found.keys() {
+            if !self.config.board.contains(*city) {
+                violations.push(format!("infection card {:?} is not on the map", city));
+            }
+        }
+        let nknown: usize = self.known_infection_runs.iter().sum();
+        if nknown > self.infection_deck.len() || self.known_infection_runs.contains(&0) {
+            violations.push(format!(
+                "known runs {:?} don't fit in an infection deck of {} cards",
+                self.known_infection_runs,
+                self.infection_deck.len()
+            ));
+        }
+    }
+
+    fn check_board_invariants(&self, violations: &mut Vec<String>) {
+        let nagents = self.agents.len();
+        if self.player_locations.len() != nagents || self.player_hands.len() != nagents {
+            violations.push(format!(
+                "{} agents but {} locations and {} hands",
+                nagents,
+                self.player_locations.len(),
+                self.player_hands.len()
+            ));
+        }
+        for (agent_idx, city) in self.player_locations.iter().enumerate() {
+            if !self.config.board.contains(*city) {
+                violations.push(format!(
+                    "player {} is in {:?}, off the map",
+                    agent_idx, city
+                ));
+            }
+        }
+        if self.current_player_i as usize >= nagents {
+            violations.push(format!(
+                "current player {} of {}",
+                self.current_player_i, nagents
+            ));
+        }
+        for city in &self.research_stations {
+            if !self.config.board.contains(*city) {
+                violations.push(format!("research station in {:?}, off the map", city));
+            }
+        }
+        let mut stations = self.research_stations.clone();
+        stations.sort();
+        stations.dedup();
+        if stations.len() != self.research_stations.len() {
+            violations.push("a city has two research stations".to_string());
+        }
+        if self.research_stations.len() > self.config.max_research_stations {
+            violations.push(format!(
+                "{} research stations, only {} exist",
+                self.research_stations.len(),
+                self.config.max_research_stations
+            ));
+        }
+        // the game ends on the last outbreak, so the counter never goes past it
+        if self.outbreaks > self.config.max_outbreaks {
+            violations.push(format!(
+                "{} outbreaks, the game ends at {}",
+                self.outbreaks, self.config.max_outbreaks
+            ));
+        }
+        if self.infection_rate_i >= self.config.infection_rates.len() {
+            violations.push(format!(
+                "infection rate index {} past the end of the track",
+                self.infection_rate_i
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::CityCard;
+    use crate::controller::{decide, RandomController};
+    use crate::game_enums::PlayerCard;
+    use crate::pandemic_game::{PandemicGameConfig, PandemicGameState};
+
+    fn new_state() -> PandemicGameState {
+        PandemicGameState::new(PandemicGameConfig::builder().seed(1).build().unwrap())
+    }
+
+    // the one violation corrupt introduces, which must mention expected
+    fn assert_violation(corrupt: impl Fn(&mut PandemicGameState), expected: &str) {
+        let mut state = new_state();
+        corrupt(&mut state);
+        let violations = state.check_invariants();
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert!(
+            violations[0].contains(expected),
+            "{:?} doesn't mention {}",
+            violations,
+            expected
+        );
+    }
+
+    #[test]
+    fn consistent_states_have_no_violations() {
+        for seed in 0..10 {
+            let config = PandemicGameConfig::builder().seed(seed).build().unwrap();
+            let mut state = PandemicGameState::new(config);
+            assert!(state.check_invariants().is_empty());
+            let mut controller = RandomController::new(seed);
+            while let Some(decision) = decide(&mut controller, &state) {
+                state.resolve(decision).unwrap();
+                assert_eq!(state.check_invariants(), Vec::<String>::new());
+            }
+        }
+    }
+
+    #[test]
+    fn cube_counts() {
+        assert_violation(
+            |state| state.cur_city_diseases[0] += 1,
+            "cubes on the board add up to",
+        );
+        assert_violation(
+            |state| {
+                state.total_cubes_on_board_per_disease[0] += 4 - state.cur_city_diseases[0] as u32;
+                state.cur_city_diseases[0] = 4;
+            },
+            "has 4",
+        );
+    }
+
+    #[test]
+    fn player_cards() {
+        assert_violation(
+            |state| {
+                let card = *state.player_deck.last().unwrap();
+                state.player_hands[0].push(card);
+            },
+            "is in player deck, hand 0",
+        );
+        assert_violation(
+            |state| {
+                let i = state
+                    .player_deck
+                    .iter()
+                    .position(|c| matches!(c, PlayerCard::CityCard(_)))
+                    .unwrap();
+                state.player_deck.remove(i);
+            },
+            "is missing",
+        );
+        assert_violation(
+            |state| {
+                let i = state
+                    .player_deck
+                    .iter()
+                    .position(|c| *c == PlayerCard::Epidemic)
+                    .unwrap();
+                state.player_hands[0].push(state.player_deck.remove(i));
+            },
+            "epidemic card in hand 0",
+        );
+        assert_violation(
+            |state| state.player_discard.push(PlayerCard::Epidemic),
+            "epidemic cards, only",
+        );
+    }
+
+    #[test]
+    fn infection_cards() {
+        assert_violation(
+            |state| {
+                state.infection_deck.pop();
+            },
+            "is missing",
+        );
+        assert_violation(
+            |state| state.known_infection_runs = vec![1000],
+            "don't fit in an infection deck",
+        );
+    }
+
+    #[test]
+    fn board() {
+        assert_violation(
+            |state| state.research_stations.push(CityCard(5000)),
+            "off the map",
+        );
+        assert_violation(
+            |state| state.player_locations[0] = CityCard(5000),
+            "off the map",
+        );
+        assert_violation(
+            |state| {
+                let city = state.research_stations[0];
+                state.research_stations.push(city);
+            },
+            "two research stations",
+        );
+        assert_violation(|state| state.current_player_i = 7, "current player 7");
+    }
+
+    #[test]
+    fn shape_mismatches_stop_the_other_checks() {
+        let mut state = new_state();
+        state.cured_diseases.pop();
+        state.cur_city_diseases.clear();
+        let violations = state.check_invariants();
+        assert_eq!(violations.len(), 2, "{:?}", violations);
+    }
+}
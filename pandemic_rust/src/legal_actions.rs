@@ -0,0 +1,553 @@
+
+use crate::action::{Action, EventAction, MoveAction};
+use crate::agent::AgentName;
+use crate::board::CityCard;
+use crate::game_enums::{EventCard, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+
+fn city_cards(hand: &[PlayerCard]) -> Vec<CityCard> {
+    hand.iter()
+        .filter_map(|c| match c {
+            PlayerCard::CityCard(city) => Some(*city),
+            _ => None,
+        })
+        .collect()
+}
+
+fn combinations(items: &[CityCard], k: usize) -> Vec<Vec<CityCard>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    let mut combs = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        for mut rest in combinations(&items[i + 1..], k - 1) {
+            rest.insert(0, *item);
+            combs.push(rest);
+        }
+    }
+    combs
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut perms = Vec::new();
+    for perm in permutations(n - 1) {
+        for i in 0..=perm.len() {
+            let mut new_perm = perm.clone();
+            new_perm.insert(i, n - 1);
+            perms.push(new_perm);
+        }
+    }
+    perms
+}
+
+impl PandemicGameState {
+    // Every valid action for agent_idx. Agents other than the current player can only play events
+    pub fn legal_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if agent_idx != self.current_player_i as usize {
+            return self.gen_event_actions(agent_idx);
+        }
+        let mut actions: Vec<Action> = self
+            .gen_move_actions(agent_idx, agent_idx)
+            .into_iter()
+            .map(|move_action| match move_action {
+                MoveAction::Drive(city) => Action::Drive(city),
+                MoveAction::DirectFlight(city) => Action::DirectFlight(city),
+                MoveAction::CharterFlight(city) => Action::CharterFlight(city),
+                MoveAction::ShuttleFlight(city) => Action::ShuttleFlight(city),
+                MoveAction::DispatchFlight(_) => unreachable!("only generated for dispatcher"),
+            })
+            .collect();
+        actions.extend(self.gen_dispatch_move_actions(agent_idx));
+        actions.extend(self.gen_operations_move_actions(agent_idx));
+        actions.extend(self.gen_treat_disease_actions(agent_idx));
+        actions.extend(self.gen_build_research_station_actions(agent_idx));
+        actions.extend(self.gen_share_knowledge_actions(agent_idx));
+        actions.extend(self.gen_discover_cure_actions(agent_idx));
+        actions.extend(self.gen_contingency_plan_actions(agent_idx));
+        actions.extend(self.gen_event_actions(agent_idx));
+        actions
+    }
+
+    // moves of agent_idx's pawn, paying for flights with card_holder_idx's cards
+    pub fn gen_move_actions(&self, agent_idx: usize, card_holder_idx: usize) -> Vec<MoveAction> {
+        let cur_city = self.player_location(agent_idx);
+        let holder_cities = city_cards(self.player_hand(card_holder_idx));
+        let mut actions: Vec<MoveAction> = self
+            .config
+            .board
+            .neighbors(cur_city)
+            .iter()
+            .map(|c| MoveAction::Drive(*c))
+            .collect();
+        actions.extend(
+            holder_cities
+                .iter()
+                .filter(|c| **c != cur_city)
+                .map(|c| MoveAction::DirectFlight(*c)),
+        );
+        if holder_cities.contains(&cur_city) {
+            actions.extend(
+                self.config
+                    .board
+                    .cities()
+                    .filter(|c| *c != cur_city)
+                    .map(MoveAction::CharterFlight),
+            );
+        }
+        if self.has_research_station(cur_city) {
+            actions.extend(
+                self.research_stations
+                    .iter()
+                    .filter(|c| **c != cur_city)
+                    .map(|c| MoveAction::ShuttleFlight(*c)),
+            );
+        }
+        actions
+    }
+
+    pub fn gen_dispatch_move_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if self.agent_name(agent_idx) != AgentName::Dispatcher {
+            return vec![];
+        }
+        let mut actions = Vec::new();
+        for other_agent_idx in 0..self.agents.len() {
+            if other_agent_idx != agent_idx {
+                for move_action in self.gen_move_actions(other_agent_idx, agent_idx) {
+                    actions.push(Action::DispatchMove {
+                        agent_idx: other_agent_idx,
+                        move_action,
+                    });
+                }
+            }
+            let cur_city = self.player_location(other_agent_idx);
+            let mut pawn_cities: Vec<CityCard> = Vec::new();
+            for i in 0..self.agents.len() {
+                let city = self.player_location(i);
+                if i != other_agent_idx && city != cur_city && !pawn_cities.contains(&city) {
+                    pawn_cities.push(city);
+                }
+            }
+            for city in pawn_cities {
+                actions.push(Action::DispatchMove {
+                    agent_idx: other_agent_idx,
+                    move_action: MoveAction::DispatchFlight(city),
+                });
+            }
+        }
+        actions
+    }
+
+    pub fn gen_operations_move_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if self.did_ops_move || self.agent_name(agent_idx) != AgentName::Operations {
+            return vec![];
+        }
+        let cur_city = self.player_location(agent_idx);
+        if !self.has_research_station(cur_city) {
+            return vec![];
+        }
+        let mut actions = Vec::new();
+        for discard in city_cards(self.player_hand(agent_idx)) {
+            for city in self.config.board.cities() {
+                if city != cur_city {
+                    actions.push(Action::OperationsMove {
+                        city,
+                        discard,
+                    });
+                }
+            }
+        }
+        actions
+    }
+
+    pub fn gen_treat_disease_actions(&self, agent_idx: usize) -> Vec<Action> {
+        let cur_city = self.player_location(agent_idx);
+        self.config
+            .board
+            .diseases()
+            .filter(|d| self.get_n_disease_cubes(cur_city, *d) > 0)
+            .map(Action::TreatDisease)
+            .collect()
+    }
+
+    pub fn gen_build_research_station_actions(&self, agent_idx: usize) -> Vec<Action> {
+        let cur_city = self.player_location(agent_idx);
+        if self.has_research_station(cur_city) {
+            return vec![];
+        }
+        if self.agent_name(agent_idx) == AgentName::Operations
+            || self
+                .player_hand(agent_idx)
+                .contains(&PlayerCard::CityCard(cur_city))
+        {
+            return self
+                .research_station_relocations()
+                .into_iter()
+                .map(|relocate_from| Action::BuildResearchStation { relocate_from })
+                .collect();
+        }
+        vec![]
+    }
+
+    // the stations that could be moved to build a new one: none while some are left in the supply
+    pub fn research_station_relocations(&self) -> Vec<Option<CityCard>> {
+        if self.research_stations.len() < self.config.max_research_stations {
+            vec![None]
+        } else {
+            self.research_stations.iter().map(|c| Some(*c)).collect()
+        }
+    }
+
+    fn shareable_cards(&self, agent_idx: usize) -> Vec<CityCard> {
+        let cur_city = self.player_location(agent_idx);
+        city_cards(self.player_hand(agent_idx))
+            .into_iter()
+            .filter(|c| *c == cur_city || self.agent_name(agent_idx) == AgentName::Researcher)
+            .collect()
+    }
+
+    pub fn gen_share_knowledge_actions(&self, agent_idx: usize) -> Vec<Action> {
+        let cur_city = self.player_location(agent_idx);
+        let mut actions = Vec::new();
+        for other_agent_idx in 0..self.agents.len() {
+            if other_agent_idx == agent_idx || self.player_location(other_agent_idx) != cur_city {
+                continue;
+            }
+            for city in self.shareable_cards(agent_idx) {
+                actions.push(Action::ShareKnowledge {
+                    giving_agent_idx: agent_idx,
+                    receiving_agent_idx: other_agent_idx,
+                    city,
+                });
+            }
+            for city in self.shareable_cards(other_agent_idx) {
+                actions.push(Action::ShareKnowledge {
+                    giving_agent_idx: other_agent_idx,
+                    receiving_agent_idx: agent_idx,
+                    city,
+                });
+            }
+        }
+        actions
+    }
+
+    pub fn gen_discover_cure_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if !self.has_research_station(self.player_location(agent_idx)) {
+            return vec![];
+        }
+        let ncards_needed = if self.agent_name(agent_idx) == AgentName::Scientist {
+            4
+        } else {
+            5
+        };
+        let hand_cities = city_cards(self.player_hand(agent_idx));
+        let mut actions = Vec::new();
+        for disease in self.config.board.diseases() {
+            if self.is_cured(disease) {
+                continue;
+            }
+            let matching_cards: Vec<CityCard> = hand_cities
+                .iter()
+                .copied()
+                .filter(|c| self.config.board.disease(*c) == disease)
+                .collect();
+            for cards in combinations(&matching_cards, ncards_needed) {
+                actions.push(Action::DiscoverCure { disease, cards });
+            }
+        }
+        actions
+    }
+
+    pub fn gen_contingency_plan_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if self.agent_name(agent_idx) != AgentName::Contingency
+            || self.contingency_planner_event_card.is_some()
+        {
+            return vec![];
+        }
+        let mut actions = Vec::new();
+        for card in &self.player_discard {
+            if let PlayerCard::EventCard(event) = card {
+                let action = Action::ContingencyPlan(*event);
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+        }
+        actions
+    }
+
+    // event cards with no possible target can't be played
+    pub fn event_has_targets(&self, event: EventCard) -> bool {
+        match event {
+            EventCard::ResilientPopulation => !self.infection_discard.is_empty(),
+            EventCard::Forecast => !self.infection_deck.is_empty(),
+            _ => true,
+        }
+    }
+
+    pub fn playable_events(&self, agent_idx: usize) -> Vec<EventCard> {
+        let mut events: Vec<EventCard> = self
+            .player_hand(agent_idx)
+            .iter()
+            .filter_map(|c| match c {
+                PlayerCard::EventCard(event) => Some(*event),
+                _ => None,
+            })
+            .collect();
+        if self.agent_name(agent_idx) == AgentName::Contingency {
+            if let Some(event) = self.contingency_planner_event_card {
+                if !events.contains(&event) {
+                    events.push(event);
+                }
+            }
+        }
+        events.retain(|event| self.event_has_targets(*event));
+        events
+    }
+
+    pub fn gen_event_actions(&self, agent_idx: usize) -> Vec<Action> {
+        if !self.config.do_events {
+            return vec![];
+        }
+        let mut events = Vec::new();
+        for card in self.playable_events(agent_idx) {
+            match card {
+                EventCard::Airlift => {
+                    for airlifted_agent_idx in 0..self.agents.len() {
+                        let cur_city = self.player_location(airlifted_agent_idx);
+                        for city in self.config.board.cities() {
+                            if city != cur_city {
+                                events.push(EventAction::Airlift {
+                                    agent_idx: airlifted_agent_idx,
+                                    city,
+                                });
+                            }
+                        }
+                    }
+                }
+                EventCard::GovernmentGrant => {
+                    for city in self.config.board.cities() {
+                        if self.has_research_station(city) {
+                            continue;
+                        }
+                        for relocate_from in self.research_station_relocations() {
+                            events.push(EventAction::GovernmentGrant {
+                                city,
+                                relocate_from,
+                            });
+                        }
+                    }
+                }
+                EventCard::ResilientPopulation => {
+                    for city in &self.infection_discard {
+                        events.push(EventAction::ResilientPopulation(*city));
+                    }
+                }
+                EventCard::Forecast => {
+                    let ncards = self.infection_deck.len().min(6);
+                    for order in permutations(ncards) {
+                        events.push(EventAction::Forecast(order));
+                    }
+                }
+                EventCard::OneQuietNight => events.push(EventAction::OneQuietNight),
+            }
+        }
+        events
+            .into_iter()
+            .map(|event| Action::PlayEvent { agent_idx, event })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use crate::pandemic_game::PandemicGameConfig;
+    use crate::ruleset::StartingPlayer;
+
+    // seat 0 is the current player
+    fn new_state(roles: &[AgentName]) -> PandemicGameState {
+        let config = PandemicGameConfig::builder()
+            .seed(0)
+            .starting_player(StartingPlayer::Fixed(0))
+            .build()
+            .unwrap();
+        let mut state = PandemicGameState::new(config);
+        state.agents = roles.iter().map(|name| Agent::new(*name)).collect();
+        state
+    }
+
+    fn give(state: &mut PandemicGameState, agent_idx: usize, card: PlayerCard) {
+        state.player_deck.retain(|c| *c != card);
+        for hand in state.player_hands.iter_mut() {
+            hand.retain(|c| *c != card);
+        }
+        state.player_hands[agent_idx].push(card);
+    }
+
+    #[test]
+    fn other_players_only_get_events() {
+        let mut state = new_state(&[
+            AgentName::Medic,
+            AgentName::Scientist,
+            AgentName::Researcher,
+            AgentName::Dispatcher,
+        ]);
+        state.player_hands[1].retain(|c| !matches!(c, PlayerCard::EventCard(_)));
+        assert!(state.legal_actions(1).is_empty());
+        give(
+            &mut state,
+            1,
+            PlayerCard::EventCard(EventCard::OneQuietNight),
+        );
+        let actions = state.legal_actions(1);
+        assert_eq!(
+            actions,
+            vec![Action::PlayEvent {
+                agent_idx: 1,
+                event: EventAction::OneQuietNight
+            }]
+        );
+        assert!(!state.legal_actions(0).is_empty());
+    }
+
+    #[test]
+    fn dispatcher_moves_other_pawns() {
+        let mut state = new_state(&[
+            AgentName::Dispatcher,
+            AgentName::Medic,
+            AgentName::Scientist,
+            AgentName::Researcher,
+        ]);
+        let start = state.player_location(1);
+        let neighbor = state.config.board.neighbors(start)[0];
+        state.player_locations[2] = neighbor;
+        let actions = state.legal_actions(0);
+        assert!(actions.contains(&Action::DispatchMove {
+            agent_idx: 1,
+            move_action: MoveAction::Drive(neighbor),
+        }));
+        assert!(actions.contains(&Action::DispatchMove {
+            agent_idx: 1,
+            move_action: MoveAction::DispatchFlight(neighbor),
+        }));
+        assert!(actions.contains(&Action::DispatchMove {
+            agent_idx: 2,
+            move_action: MoveAction::DispatchFlight(start),
+        }));
+        // the dispatcher can't send a pawn to the city it's already in
+        assert!(!actions.contains(&Action::DispatchMove {
+            agent_idx: 1,
+            move_action: MoveAction::DispatchFlight(start),
+        }));
+
+        let mut state = new_state(&[
+            AgentName::Medic,
+            AgentName::Dispatcher,
+            AgentName::Scientist,
+            AgentName::Researcher,
+        ]);
+        state.player_locations[2] = neighbor;
+        assert!(!state
+            .legal_actions(0)
+            .iter()
+            .any(|a| matches!(a, Action::DispatchMove { .. })));
+    }
+
+    #[test]
+    fn operations_expert_moves_once_per_turn_and_builds_without_a_card() {
+        let mut state = new_state(&[
+            AgentName::Operations,
+            AgentName::Medic,
+            AgentName::Scientist,
+            AgentName::Researcher,
+        ]);
+        let start = state.player_location(0);
+        assert!(state.has_research_station(start));
+        let ncity_cards = city_cards(state.player_hand(0)).len();
+        assert!(ncity_cards > 0);
+        let ops_moves = |state: &PandemicGameState| {
+            state
+                .legal_actions(0)
+                .into_iter()
+                .filter(|a| matches!(a, Action::OperationsMove { .. }))
+                .count()
+        };
+        assert_eq!(
+            ops_moves(&state),
+            ncity_cards * (state.config.board.ncities() - 1)
+        );
+        state.did_ops_move = true;
+        assert_eq!(ops_moves(&state), 0);
+
+        let neighbor = state.config.board.neighbors(start)[0];
+        state.player_locations[0] = neighbor;
+        state.player_hands[0].retain(|c| *c != PlayerCard::CityCard(neighbor));
+        assert!(state
+            .legal_actions(0)
+            .contains(&Action::BuildResearchStation {
+                relocate_from: None
+            }));
+    }
+
+    #[test]
+    fn researcher_shares_any_city_card() {
+        let mut state = new_state(&[
+            AgentName::Medic,
+            AgentName::Researcher,
+            AgentName::Scientist,
+            AgentName::Dispatcher,
+        ]);
+        let start = state.player_location(0);
+        let elsewhere = state.config.board.neighbors(start)[0];
+        give(&mut state, 1, PlayerCard::CityCard(elsewhere));
+        let actions = state.legal_actions(0);
+        assert!(actions.contains(&Action::ShareKnowledge {
+            giving_agent_idx: 1,
+            receiving_agent_idx: 0,
+            city: elsewhere,
+        }));
+
+        // anyone else can only give the card of the city they're in
+        give(&mut state, 2, PlayerCard::CityCard(elsewhere));
+        assert!(!state.legal_actions(0).contains(&Action::ShareKnowledge {
+            giving_agent_idx: 2,
+            receiving_agent_idx: 0,
+            city: elsewhere,
+        }));
+    }
+
+    #[test]
+    fn forecast_offers_every_order_of_six_cards() {
+        let mut state = new_state(&[
+            AgentName::Medic,
+            AgentName::Scientist,
+            AgentName::Researcher,
+            AgentName::Dispatcher,
+        ]);
+        give(&mut state, 0, PlayerCard::EventCard(EventCard::Forecast));
+        let mut orders: Vec<Vec<usize>> = state
+            .legal_actions(0)
+            .into_iter()
+            .filter_map(|a| match a {
+                Action::PlayEvent {
+                    event: EventAction::Forecast(order),
+                    ..
+                } => Some(order),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(orders.len(), 720);
+        orders.sort();
+        orders.dedup();
+        assert_eq!(orders.len(), 720);
+        assert!(orders.iter().all(|order| {
+            let mut sorted = order.clone();
+            sorted.sort();
+            sorted == (0..6).collect::<Vec<usize>>()
+        }));
+    }
+}
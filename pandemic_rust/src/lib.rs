@@ -1,7 +1,20 @@
 #![feature(variant_count)]
+pub mod action;
 pub mod agent;
+pub mod board;
+pub mod controller;
 pub mod game_enums;
+pub mod interactive;
+pub mod invariants;
+pub mod legal_actions;
+pub mod map_generator;
+pub mod map_validation;
+pub mod mcts;
 pub mod pandemic_game;
+pub mod rollout_ai;
+pub mod ruleset;
+pub mod scheduler;
+pub mod snapshot;
 extern crate num;
 #[macro_use]
 extern crate num_derive;
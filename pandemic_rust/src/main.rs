@@ -1,3 +1,4 @@
+use pandemic_rust::action::Action;
 use pandemic_rust::agent::{ActionEndState, AgentName};
 use pandemic_rust::pandemic_game::{PandemicGameConfig, PandemicGameState};
 
@@ -11,10 +12,7 @@ fn check_result(expect_err: bool, result: Result<(), ActionEndState>) {
         }
         Err(result) => match result {
             ActionEndState::Err(err) => {
-                if expect_err {
-                    println!("expected err but found no error");
-                    panic!()
-                } else {
+                if !expect_err {
                     println!("didn't expect err but found: {}", err.msg);
                     panic!()
                 }
@@ -30,17 +28,12 @@ fn check_result(expect_err: bool, result: Result<(), ActionEndState>) {
 }
 
 fn main() {
-    let config = PandemicGameConfig::new(
-        4,
-        None,
-        None,
-        None,
-        None,
-        None,
-        Some(true),
-        Some(true),
-        Some(false),
-    );
+    let config = PandemicGameConfig::builder()
+        .nplayers(4)
+        .seed(0)
+        .do_events(false)
+        .build()
+        .unwrap();
     let mut state = PandemicGameState::new(config);
     println!("infection_deck: {:#?}", state.infection_deck);
     println!("game: {}", state.config);
@@ -52,14 +45,22 @@ fn main() {
         );
 
         let mut expect_err = false;
-        let result = match state.agents[i as usize].agent_type {
-            AgentName::Contingency => state.do_action(i as usize, 0),
-            AgentName::Dispatcher => state.do_action(i as usize, 0),
+        let neighbor = state
+            .config
+            .board
+            .neighbors(state.player_location(i as usize))[0];
+        let action = match state.agents[i as usize].agent_type {
+            AgentName::Contingency => Action::Drive(neighbor),
+            AgentName::Dispatcher => Action::Drive(neighbor),
             _ => {
                 expect_err = true;
-                state.do_action(i as usize, 0)
+                Action::BuildResearchStation {
+                    relocate_from: None,
+                }
             }
         };
+        println!("action: {}", serde_json::to_string(&action).unwrap());
+        let result = state.do_action(i as usize, action);
         check_result(expect_err, result);
     }
 }
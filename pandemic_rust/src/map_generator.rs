@@ -0,0 +1,460 @@
+use std::collections::HashSet;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::board::{Board, MapCity, MapDisease, MapError, MapFile};
+use crate::map_validation::{CARDS_TO_CURE, DEFAULT_DISEASE_CUBES, SETUP_INFECTIONS};
+
+// the colors generated maps name their diseases after, in order
+pub const DISEASE_COLORS: [&str; 8] = [
+    "blue", "red", "black", "yellow", "purple", "green", "orange", "white",
+];
+
+// maps that fail validation, e.g. a color region grown too small, are thrown away and redrawn
+const MAX_ATTEMPTS: usize = 100;
+
+const SYLLABLES: [&str; 24] = [
+    "ka", "lo", "mi", "ra", "ten", "su", "vo", "dar", "ne", "po", "bel", "ia", "gor", "an", "ti",
+    "mar", "os", "ze", "lin", "ha", "qu", "ber", "do", "es",
+];
+
+// What a generated map looks like. Cities are placed at random in the unit square, which the
+// coordinates of the generated map are in
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapParams {
+    pub name: String,
+    pub ncities: usize,
+    pub ncolors: usize,
+    // edges per city, ocean edges included. The bundled map has 3.875
+    pub average_degree: f64,
+    // 1 joins the closest cities first, giving the triangles of the real map, 0 picks at random
+    // among nearby cities
+    pub clustering: f64,
+    // no two edges cross, apart from ocean edges
+    pub planar: bool,
+    // edges between far apart cities, like the Pacific crossings of the real map
+    pub ocean_edges: usize,
+    // each color's cities form one region instead of being scattered over the map
+    pub contiguous_colors: bool,
+    pub seed: u64,
+}
+
+impl Default for MapParams {
+    fn default() -> Self {
+        MapParams {
+            name: "generated".to_string(),
+            ncities: 48,
+            ncolors: 4,
+            average_degree: 3.875,
+            clustering: 0.8,
+            planar: true,
+            ocean_edges: 3,
+            contiguous_colors: true,
+            seed: 0,
+        }
+    }
+}
+
+impl MapParams {
+    fn nedges(&self) -> usize {
+        (self.average_degree * self.ncities as f64 / 2.0).round() as usize
+    }
+
+    fn check(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.ncolors == 0 || self.ncolors > DISEASE_COLORS.len() {
+            problems.push(format!(
+                "{} colors, between 1 and {} supported",
+                self.ncolors,
+                DISEASE_COLORS.len()
+            ));
+        }
+        if self.ncities < self.ncolors * CARDS_TO_CURE {
+            problems.push(format!(
+                "{} cities for {} colors, a cure takes {} cards of a color",
+                self.ncities, self.ncolors, CARDS_TO_CURE
+            ));
+        }
+        if self.ncities < SETUP_INFECTIONS {
+            problems.push(format!(
+                "{} cities, setup infects {}",
+                self.ncities, SETUP_INFECTIONS
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.clustering) {
+            problems.push(format!(
+                "clustering {} is not between 0 and 1",
+                self.clustering
+            ));
+        }
+        let n = self.ncities;
+        let nedges = self.nedges();
+        // connected takes a spanning tree, the rest must fit between distinct pairs of cities
+        if n > 0 && nedges < n - 1 + self.ocean_edges {
+            problems.push(format!(
+                "average degree {} is too low to connect {} cities with {} ocean edges",
+                self.average_degree, n, self.ocean_edges
+            ));
+        }
+        let max_local = if self.planar && n >= 3 {
+            3 * n - 6
+        } else {
+            n * n.saturating_sub(1) / 2
+        };
+        if nedges > max_local + self.ocean_edges {
+            problems.push(format!(
+                "average degree {} is too high for {} cities{}",
+                self.average_degree,
+                n,
+                if self.planar { " on a planar map" } else { "" }
+            ));
+        }
+        problems
+    }
+}
+
+// A playable map, checked by the same validation as maps loaded from a file
+pub fn generate_map(params: &MapParams) -> Result<Board, MapError> {
+    let problems = params.check();
+    if !problems.is_empty() {
+        return Result::Err(MapError { problems });
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+    let mut error = None;
+    for _ in 0..MAX_ATTEMPTS {
+        match Board::from_map(&draw_map(params, &mut rng)) {
+            Result::Ok(board) => return Result::Ok(board),
+            Result::Err(e) => error = Some(e),
+        }
+    }
+    Result::Err(error.unwrap())
+}
+
+fn draw_map(params: &MapParams, rng: &mut ChaCha8Rng) -> MapFile {
+    let n = params.ncities;
+    let points = place_cities(n, rng);
+    let names = city_names(n, rng);
+    let mut adjacent = vec![HashSet::new(); n];
+
+    let mut pairs: Vec<(usize, usize)> = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .collect();
+    let length = |&(a, b): &(usize, usize)| distance(points[a], points[b]);
+
+    // A Euclidean minimum spanning tree keeps the map connected, and its edges never cross
+    pairs.sort_by(|x, y| length(x).total_cmp(&length(y)));
+    let mut components: Vec<usize> = (0..n).collect();
+    let mut local = Vec::new();
+    for &(a, b) in &pairs {
+        let (ca, cb) = (find(&mut components, a), find(&mut components, b));
+        if ca != cb {
+            components[ca] = cb;
+            local.push((a, b));
+        }
+    }
+
+    // then the shortest remaining pairs, with noise in what counts as short for low clustering
+    let nlocal = params.nedges() - params.ocean_edges;
+    let noise = 2.0 * (1.0 - params.clustering);
+    let mut extra: Vec<((usize, usize), f64)> = pairs
+        .iter()
+        .map(|pair| (*pair, length(pair) * (rng.gen::<f64>() * noise).exp()))
+        .collect();
+    extra.sort_by(|x, y| x.1.total_cmp(&y.1));
+    let tree: HashSet<(usize, usize)> = local.iter().copied().collect();
+    for ((a, b), _) in extra {
+        if local.len() >= nlocal {
+            break;
+        }
+        if tree.contains(&(a, b)) {
+            continue;
+        }
+        let crossing = params.planar
+            && local.iter().any(|&(c, d)| {
+                crosses(
+                    (points[a], points[b]),
+                    (points[c], points[d]),
+                    [a, b, c, d],
+                )
+            });
+        if !crossing {
+            local.push((a, b));
+        }
+    }
+    for &(a, b) in &local {
+        adjacent[a].insert(b);
+        adjacent[b].insert(a);
+    }
+    let colors = if params.contiguous_colors {
+        grow_color_regions(params.ncolors, &points, &adjacent, rng)
+    } else {
+        let mut colors: Vec<usize> = (0..n).map(|i| i % params.ncolors).collect();
+        colors.shuffle(rng);
+        colors
+    };
+
+    // ocean edges from the longest third of the pairs not already joined
+    let mut far: Vec<(usize, usize)> = pairs
+        .iter()
+        .rev()
+        .filter(|(a, b)| !adjacent[*a].contains(b))
+        .copied()
+        .collect();
+    far.truncate(far.len() / 3);
+    for &(a, b) in far.choose_multiple(rng, params.ocean_edges) {
+        adjacent[a].insert(b);
+        adjacent[b].insert(a);
+    }
+
+    let center = (0..n)
+        .min_by(|&a, &b| {
+            distance(points[a], (0.5, 0.5)).total_cmp(&distance(points[b], (0.5, 0.5)))
+        })
+        .unwrap();
+    MapFile {
+        name: params.name.clone(),
+        starting_city: names[center].clone(),
+        diseases: DISEASE_COLORS[..params.ncolors]
+            .iter()
+            .map(|name| MapDisease {
+                name: name.to_string(),
+                cubes: DEFAULT_DISEASE_CUBES,
+            })
+            .collect(),
+        cities: (0..n)
+            .map(|i| {
+                let mut neighbors: Vec<usize> = adjacent[i].iter().copied().collect();
+                neighbors.sort_by(|&a, &b| {
+                    distance(points[i], points[a]).total_cmp(&distance(points[i], points[b]))
+                });
+                MapCity {
+                    name: names[i].clone(),
+                    disease: DISEASE_COLORS[colors[i]].to_string(),
+                    neighbors: neighbors.into_iter().map(|j| names[j].clone()).collect(),
+                    coordinates: Some((round(points[i].0), round(points[i].1))),
+                }
+            })
+            .collect(),
+    }
+}
+
+// spread out, so that no two cities sit on top of each other
+fn place_cities(n: usize, rng: &mut ChaCha8Rng) -> Vec<(f64, f64)> {
+    let spacing = 0.5 / (n as f64).sqrt();
+    let mut points: Vec<(f64, f64)> = Vec::with_capacity(n);
+    while points.len() < n {
+        let mut point = (rng.gen(), rng.gen());
+        for _ in 0..30 {
+            if points.iter().all(|p| distance(*p, point) >= spacing) {
+                break;
+            }
+            point = (rng.gen(), rng.gen());
+        }
+        points.push(point);
+    }
+    points
+}
+
+// capitalized and unique, like the bundled map's
+fn city_names(n: usize, rng: &mut ChaCha8Rng) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut names = Vec::with_capacity(n);
+    while names.len() < n {
+        let nsyllables = 2 + seen.len() / 400 + rng.gen_range(0..2);
+        let name: String = (0..nsyllables)
+            .map(|_| *SYLLABLES.choose(rng).unwrap())
+            .collect();
+        if seen.insert(name.clone()) {
+            names.push(name[..1].to_uppercase() + &name[1..]);
+        }
+    }
+    names
+}
+
+// Each color starts from a city far from the others' and takes turns claiming the unclaimed
+// neighbor of its region closest to where it started, until it has its share of the cities
+fn grow_color_regions(
+    ncolors: usize,
+    points: &[(f64, f64)],
+    adjacent: &[HashSet<usize>],
+    rng: &mut ChaCha8Rng,
+) -> Vec<usize> {
+    let n = points.len();
+    let mut seeds = vec![rng.gen_range(0..n)];
+    while seeds.len() < ncolors {
+        let farthest = (0..n)
+            .filter(|i| !seeds.contains(i))
+            .max_by(|&a, &b| {
+                let nearest_seed =
+                    |i: usize| seeds.iter().map(|&s| distance(points[i], points[s])).fold(f64::MAX, f64::min);
+                nearest_seed(a).total_cmp(&nearest_seed(b))
+            })
+            .unwrap();
+        seeds.push(farthest);
+    }
+    let shares: Vec<usize> = (0..ncolors)
+        .map(|c| n / ncolors + usize::from(c < n % ncolors))
+        .collect();
+
+    let mut colors: Vec<Option<usize>> = vec![None; n];
+    let mut sizes = vec![1; ncolors];
+    for (color, &seed) in seeds.iter().enumerate() {
+        colors[seed] = Some(color);
+    }
+    let mut nclaimed = ncolors;
+    // a color walled in by the others stops early, and the cities left over once every color
+    // is full or stuck go to a neighboring region
+    let mut respect_shares = true;
+    while nclaimed < n {
+        let mut claimed_any = false;
+        for color in 0..ncolors {
+            if respect_shares && sizes[color] >= shares[color] {
+                continue;
+            }
+            let frontier = (0..n).filter(|&i| {
+                colors[i].is_none() && adjacent[i].iter().any(|&j| colors[j] == Some(color))
+            });
+            let seed = points[seeds[color]];
+            let next = frontier.min_by(|&a, &b| {
+                distance(points[a], seed).total_cmp(&distance(points[b], seed))
+            });
+            if let Some(city) = next {
+                colors[city] = Some(color);
+                sizes[color] += 1;
+                nclaimed += 1;
+                claimed_any = true;
+            }
+        }
+        if !claimed_any {
+            respect_shares = false;
+        }
+    }
+    colors.into_iter().map(|c| c.unwrap()).collect()
+}
+
+fn find(components: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while components[root] != root {
+        root = components[root];
+    }
+    components[i] = root;
+    root
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn round(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+// whether two edges cross somewhere other than a city they share
+fn crosses(
+    (p1, p2): ((f64, f64), (f64, f64)),
+    (q1, q2): ((f64, f64), (f64, f64)),
+    [a, b, c, d]: [usize; 4],
+) -> bool {
+    if a == c || a == d || b == c || b == d {
+        return false;
+    }
+    let orientation = |o: (f64, f64), p: (f64, f64), q: (f64, f64)| {
+        ((p.0 - o.0) * (q.1 - o.1) - (p.1 - o.1) * (q.0 - o.0)).signum()
+    };
+    orientation(p1, p2, q1) != orientation(p1, p2, q2)
+        && orientation(q1, q2, p1) != orientation(q1, q2, p2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_map() {
+        let params = MapParams {
+            seed: 7,
+            ..MapParams::default()
+        };
+        let map = generate_map(&params).unwrap();
+        assert_eq!(generate_map(&params).unwrap(), map);
+        let other = generate_map(&MapParams {
+            seed: 8,
+            ..params.clone()
+        })
+        .unwrap();
+        assert_ne!(other, map);
+    }
+
+    #[test]
+    fn generated_maps_load() {
+        for params in [
+            MapParams::default(),
+            MapParams {
+                ncities: 30,
+                ncolors: 6,
+                planar: false,
+                contiguous_colors: false,
+                seed: 1,
+                ..MapParams::default()
+            },
+            MapParams {
+                ncities: 100,
+                ncolors: 8,
+                clustering: 0.0,
+                ocean_edges: 0,
+                seed: 2,
+                ..MapParams::default()
+            },
+        ] {
+            let board = generate_map(&params).unwrap();
+            assert_eq!(board.ncities(), params.ncities);
+            assert_eq!(board.ndiseases(), params.ncolors);
+            let map = board.to_map();
+            assert_eq!(Board::from_map(&map).unwrap(), board);
+            assert_eq!(Board::from_json(&board.to_json()).unwrap(), board);
+        }
+    }
+
+    #[test]
+    fn impossible_params_are_refused() {
+        let problems = |params: MapParams| generate_map(&params).unwrap_err().problems;
+
+        let too_few = problems(MapParams {
+            ncities: 8,
+            ..MapParams::default()
+        });
+        assert!(too_few.iter().any(|p| p.contains("a cure takes")));
+        assert!(too_few.iter().any(|p| p.contains("setup infects")));
+
+        let too_many_colors = problems(MapParams {
+            ncolors: DISEASE_COLORS.len() + 1,
+            ncities: 100,
+            ..MapParams::default()
+        });
+        assert_eq!(too_many_colors.len(), 1, "{:?}", too_many_colors);
+        assert!(too_many_colors[0].contains("colors"));
+
+        let too_low = problems(MapParams {
+            average_degree: 1.0,
+            ..MapParams::default()
+        });
+        assert_eq!(too_low.len(), 1, "{:?}", too_low);
+        assert!(too_low[0].contains("too low"));
+
+        let too_high = problems(MapParams {
+            average_degree: 6.0,
+            ..MapParams::default()
+        });
+        assert_eq!(too_high.len(), 1, "{:?}", too_high);
+        assert!(too_high[0].contains("too high"));
+        // without planarity the same degree fits
+        assert!(generate_map(&MapParams {
+            average_degree: 6.0,
+            planar: false,
+            ..MapParams::default()
+        })
+        .is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+// Checks on a map described by city names only, so that build.rs can run them on the bundled map
+// with the same code the runtime loader uses. Std only: build.rs includes this file directly
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// city cards of one color a player discards to cure it
+pub const CARDS_TO_CURE: usize = 5;
+// infection cards drawn at setup, three each for 3, 2 and 1 cubes
+pub const SETUP_INFECTIONS: usize = 9;
+// cubes of each color in the box, unless the map says otherwise
+pub const DEFAULT_DISEASE_CUBES: u32 = 24;
+
+pub struct MapCitySpec<'a> {
+    pub name: &'a str,
+    // None if the map has no color for the city
+    pub disease: Option<&'a str>,
+    pub neighbors: Vec<&'a str>,
+}
+
+pub struct MapSpec<'a> {
+    pub starting_city: &'a str,
+    pub cities: Vec<MapCitySpec<'a>>,
+    // the diseases a game on this map is played with
+    pub diseases: Vec<&'a str>,
+}
+
+// Everything wrong with the map, one message per problem. Empty for a playable map
+pub fn validate_map(map: &MapSpec) -> Vec<String> {
+    let mut problems = Vec::new();
+    if map.cities.is_empty() {
+        problems.push("the map has no cities".to_string());
+        return problems;
+    }
+
+    if map.cities.len() < SETUP_INFECTIONS {
+        problems.push(format!(
+            "the map has {} cities, setup infects {}",
+            map.cities.len(),
+            SETUP_INFECTIONS
+        ));
+    }
+
+    let mut cities: HashMap<&str, &MapCitySpec> = HashMap::new();
+    for city in &map.cities {
+        if cities.insert(city.name, city).is_some() {
+            problems.push(format!("{} is listed twice", city.name));
+        }
+    }
+    if !cities.contains_key(map.starting_city) {
+        problems.push(format!(
+            "starting city {} is not on the map",
+            map.starting_city
+        ));
+    }
+
+    if map.diseases.is_empty() {
+        problems.push("the map has no diseases".to_string());
+    }
+    for (i, disease) in map.diseases.iter().enumerate() {
+        if map.diseases[..i]
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(disease))
+        {
+            problems.push(format!("disease {} is listed twice", disease));
+        }
+    }
+
+    let mut ncities_per_disease: HashMap<&str, usize> = HashMap::new();
+    for city in &map.cities {
+        match city.disease {
+            None => problems.push(format!("{} has no disease color", city.name)),
+            Some(disease) => match map
+                .diseases
+                .iter()
+                .find(|d| d.eq_ignore_ascii_case(disease))
+            {
+                Some(d) => *ncities_per_disease.entry(*d).or_insert(0) += 1,
+                None => problems.push(format!(
+                    "{} has unknown disease {}, expected one of {}",
+                    city.name,
+                    disease,
+                    map.diseases.join(", ")
+                )),
+            },
+        }
+
+        let mut seen = HashSet::new();
+        for neighbor in &city.neighbors {
+            if !seen.insert(*neighbor) {
+                problems.push(format!("{} lists {} twice", city.name, neighbor));
+                continue;
+            }
+            if *neighbor == city.name {
+                problems.push(format!("{} is its own neighbor", city.name));
+                continue;
+            }
+            match cities.get(neighbor) {
+                None => problems.push(format!(
+                    "{} has neighbor {}, which is not on the map",
+                    city.name, neighbor
+                )),
+                // outbreaks spread along both directions of every edge
+                Some(other) if !other.neighbors.contains(&city.name) => problems.push(format!(
+                    "{} lists {} as a neighbor but {} doesn't list {}",
+                    city.name, neighbor, neighbor, city.name
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    for disease in &map.diseases {
+        let ncities = ncities_per_disease.get(disease).copied().unwrap_or(0);
+        if ncities < CARDS_TO_CURE {
+            problems.push(format!(
+                "{} has {} cities, a cure takes {} cards",
+                disease, ncities, CARDS_TO_CURE
+            ));
+        }
+    }
+
+    // edges are followed both ways so an asymmetric edge reported above doesn't also show up here
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for city in &map.cities {
+        for neighbor in &city.neighbors {
+            if cities.contains_key(neighbor) {
+                edges.entry(city.name).or_default().push(neighbor);
+                edges.entry(neighbor).or_default().push(city.name);
+            }
+        }
+    }
+    let start = map.cities[0].name;
+    let mut reached = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(city) = queue.pop_front() {
+        for neighbor in edges.get(city).into_iter().flatten() {
+            if reached.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    let unreached: Vec<&str> = map
+        .cities
+        .iter()
+        .map(|c| c.name)
+        .filter(|name| !reached.contains(name))
+        .collect();
+    if !unreached.is_empty() {
+        problems.push(format!(
+            "the map is not connected, {} can't be reached from {}",
+            unreached.join(", "),
+            start
+        ));
+    }
+    problems
+}
+
+// The diseases of a map that doesn't list them, in the order its cities first use them
+pub fn diseases_of_cities<'a>(city_diseases: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut diseases: Vec<&str> = Vec::new();
+    for disease in city_diseases {
+        if !diseases.iter().any(|d| d.eq_ignore_ascii_case(disease)) {
+            diseases.push(disease);
+        }
+    }
+    diseases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cities c0..c9 in a ring, all one color
+    fn ring_names() -> Vec<String> {
+        (0..10).map(|i| format!("c{}", i)).collect()
+    }
+
+    fn ring(names: &[String]) -> MapSpec<'_> {
+        let n = names.len();
+        MapSpec {
+            starting_city: &names[0],
+            cities: (0..n)
+                .map(|i| MapCitySpec {
+                    name: &names[i],
+                    disease: Some("blue"),
+                    neighbors: vec![&names[(i + n - 1) % n], &names[(i + 1) % n]],
+                })
+                .collect(),
+            diseases: vec!["blue"],
+        }
+    }
+
+    #[test]
+    fn ring_is_playable() {
+        let names = ring_names();
+        assert_eq!(validate_map(&ring(&names)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn one_way_edge() {
+        let names = ring_names();
+        let mut map = ring(&names);
+        // c1 still lists c0
+        map.cities[0].neighbors.retain(|n| *n != "c1");
+        assert_eq!(
+            validate_map(&map),
+            vec!["c1 lists c0 as a neighbor but c0 doesn't list c1".to_string()]
+        );
+    }
+
+    #[test]
+    fn unknown_neighbor() {
+        let names = ring_names();
+        let mut map = ring(&names);
+        map.cities[3].neighbors.push("atlantis");
+        assert_eq!(
+            validate_map(&map),
+            vec!["c3 has neighbor atlantis, which is not on the map".to_string()]
+        );
+    }
+
+    #[test]
+    fn disconnected() {
+        let names = ring_names();
+        let mut map = ring(&names);
+        // cut the ring in two places, between c9 and c0 and between c4 and c5
+        map.cities[0].neighbors.retain(|n| *n != "c9");
+        map.cities[9].neighbors.retain(|n| *n != "c0");
+        map.cities[4].neighbors.retain(|n| *n != "c5");
+        map.cities[5].neighbors.retain(|n| *n != "c4");
+        let problems = validate_map(&map);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with("the map is not connected, c5, c6, c7, c8, c9"));
+    }
+
+    #[test]
+    fn too_few_cities_of_a_color() {
+        let names = ring_names();
+        let mut map = ring(&names);
+        map.diseases.push("red");
+        for city in &mut map.cities[..4] {
+            city.disease = Some("red");
+        }
+        assert_eq!(
+            validate_map(&map),
+            vec!["red has 4 cities, a cure takes 5 cards".to_string()]
+        );
+    }
+}
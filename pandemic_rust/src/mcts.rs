@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::action::Action;
+use crate::board::CityCard;
+use crate::controller::{decide, Controller};
+use crate::game_enums::{GameEnd, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+use crate::rollout_ai::{estimate_board_state_score, least_useful_cards};
+use crate::scheduler::{Decision, EventWindow, PendingDecision, TurnPhase};
+
+#[derive(Debug, Copy, Clone)]
+pub enum SearchBudget {
+    // total iterations, split across threads
+    Iterations(u64),
+    Time(Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionStats {
+    pub action: Action,
+    pub visits: u64,
+    pub mean_reward: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    // the most visited action
+    pub action: Action,
+    pub iterations: u64,
+    // root actions, most visited first
+    pub stats: Vec<ActionStats>,
+}
+
+// 1 for a win, 0 for a loss, and in between for a game still going, by the rollout AI's heuristic
+fn reward(state: &PandemicGameState) -> f64 {
+    match state.phase {
+        TurnPhase::GameOver(GameEnd::Win) => 1.0,
+        TurnPhase::GameOver(_) => 0.0,
+        _ => (0.5 - estimate_board_state_score(state)).clamp(0.0, 1.0),
+    }
+}
+
+fn turn_actions(state: &PandemicGameState, agent_idx: usize) -> Vec<Action> {
+    state
+        .legal_actions(agent_idx)
+        .into_iter()
+        .filter(|a| a.uses_action())
+        .collect()
+}
+
+// Plays random turn actions, keeps the most useful cards and never plays events. Used for
+// everything the tree doesn't decide
+struct RolloutPolicy {
+    rng: ChaCha8Rng,
+}
+
+impl Controller for RolloutPolicy {
+    fn choose_action(&mut self, state: &PandemicGameState, agent_idx: usize) -> Action {
+        turn_actions(state, agent_idx)
+            .choose(&mut self.rng)
+            .expect("there is always a drive action")
+            .clone()
+    }
+
+    fn choose_discards(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        ndiscards: usize,
+    ) -> Vec<PlayerCard> {
+        least_useful_cards(state, agent_idx, ndiscards)
+    }
+
+    fn choose_event(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idxs: &[usize],
+        _window: EventWindow,
+    ) -> Option<Decision> {
+        None
+    }
+
+    fn choose_forecast_order(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> Vec<usize> {
+        (0..cards.len()).collect()
+    }
+
+    fn choose_resilient_population_target(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> CityCard {
+        cards[0]
+    }
+}
+
+// visits and total reward of each root action
+type RootStats = Vec<(Action, u64, f64)>;
+
+struct Node {
+    visits: u64,
+    total_reward: f64,
+    children: Vec<(Action, usize)>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            visits: 0,
+            total_reward: 0.0,
+            children: Vec::new(),
+        }
+    }
+}
+
+// An open loop tree: nodes are sequences of turn actions rather than game states, so every
+// iteration can sample different draws and still share statistics
+struct Tree {
+    nodes: Vec<Node>,
+    rng: ChaCha8Rng,
+    policy: RolloutPolicy,
+    exploration: f64,
+    rollout_turns: u32,
+}
+
+impl Tree {
+    fn new(seed: u64, stream: u64, exploration: f64, rollout_turns: u32) -> Tree {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        let policy = RolloutPolicy {
+            rng: ChaCha8Rng::seed_from_u64(rng.gen()),
+        };
+        Tree {
+            nodes: vec![Node::new()],
+            rng,
+            policy,
+            exploration,
+            rollout_turns,
+        }
+    }
+
+    // apply a turn action, then let the rollout policy resolve whatever comes before the next one
+    fn step(&mut self, state: &mut PandemicGameState, action: Action) {
+        state
+            .resolve(Decision::Action(action))
+            .expect("tree actions are legal");
+        while !matches!(
+            state.next_decision(),
+            PendingDecision::ChooseAction { .. } | PendingDecision::GameOver(_)
+        ) {
+            let decision = decide(&mut self.policy, state).expect("game is not over");
+            state
+                .resolve(decision)
+                .expect("rollout decisions are legal");
+        }
+    }
+
+    fn select_child(&self, node: usize, actions: &[Action]) -> (Action, usize) {
+        let ln_visits = (self.nodes[node].visits as f64).ln();
+        let mut best: Option<(f64, &(Action, usize))> = None;
+        for child in &self.nodes[node].children {
+            if !actions.contains(&child.0) {
+                continue;
+            }
+            let child_node = &self.nodes[child.1];
+            let ucb = child_node.total_reward / child_node.visits as f64
+                + self.exploration * (ln_visits / child_node.visits as f64).sqrt();
+            if best.is_none_or(|(best_ucb, _)| ucb > best_ucb) {
+                best = Some((ucb, child));
+            }
+        }
+        best.expect("every legal action is expanded").1.clone()
+    }
+
+    fn iterate(&mut self, root_state: &PandemicGameState) {
+        // chance nodes: each iteration follows one sample of the player and infection decks
+        let mut state = root_state.clone();
+        state.sample_hidden_cards(self.rng.gen());
+
+        let mut path = vec![0];
+        let mut node = 0;
+        // selection and expansion
+        while let PendingDecision::ChooseAction { agent_idx, .. } = state.next_decision() {
+            let actions = turn_actions(&state, agent_idx);
+            let unexpanded: Vec<&Action> = actions
+                .iter()
+                .filter(|a| self.nodes[node].children.iter().all(|(c, _)| c != *a))
+                .collect();
+            if let Some(action) = unexpanded.choose(&mut self.rng) {
+                let action = (*action).clone();
+                let child = self.nodes.len();
+                self.nodes.push(Node::new());
+                self.nodes[node].children.push((action.clone(), child));
+                self.step(&mut state, action);
+                path.push(child);
+                break;
+            }
+            let (action, child) = self.select_child(node, &actions);
+            self.step(&mut state, action);
+            path.push(child);
+            node = child;
+        }
+
+        // rollout
+        let mut turns = 0;
+        while turns < self.rollout_turns {
+            let current_player_i = state.current_player_i;
+            match decide(&mut self.policy, &state) {
+                Some(decision) => state
+                    .resolve(decision)
+                    .expect("rollout decisions are legal"),
+                None => break,
+            }
+            if state.current_player_i != current_player_i {
+                turns += 1;
+            }
+        }
+
+        // backpropagation
+        let reward = reward(&state);
+        for node in path {
+            self.nodes[node].visits += 1;
+            self.nodes[node].total_reward += reward;
+        }
+    }
+
+    fn root_stats(&self) -> RootStats {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|(action, child)| {
+                let node = &self.nodes[*child];
+                (action.clone(), node.visits, node.total_reward)
+            })
+            .collect()
+    }
+}
+
+// UCT search over turn actions. Each thread grows its own tree from a copy of the game and the
+// root statistics are merged, so threads never wait on each other
+pub struct MctsAI {
+    pub nthreads: usize,
+    pub budget: SearchBudget,
+    // UCT exploration constant
+    pub exploration: f64,
+    // turns played by the rollout policy before a leaf is scored
+    pub rollout_turns: u32,
+    seed: u64,
+}
+
+impl MctsAI {
+    pub fn new(budget: SearchBudget, nthreads: usize, seed: u64) -> MctsAI {
+        MctsAI {
+            nthreads: nthreads.max(1),
+            budget,
+            exploration: 2f64.sqrt(),
+            rollout_turns: 1,
+            seed,
+        }
+    }
+
+    pub fn search(&mut self, state: &PandemicGameState) -> SearchResult {
+        let seed = self.seed;
+        self.seed = self.seed.wrapping_add(1);
+        let deadline = match self.budget {
+            SearchBudget::Time(duration) => Some(Instant::now() + duration),
+            SearchBudget::Iterations(_) => None,
+        };
+        let nthreads = self.nthreads;
+        let thread_results: Vec<(u64, RootStats)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..nthreads)
+                .map(|i| {
+                    let niterations = match self.budget {
+                        SearchBudget::Iterations(n) => {
+                            n / nthreads as u64 + u64::from((i as u64) < n % nthreads as u64)
+                        }
+                        SearchBudget::Time(_) => u64::MAX,
+                    };
+                    let mut tree = Tree::new(seed, i as u64, self.exploration, self.rollout_turns);
+                    scope.spawn(move || {
+                        let mut iterations = 0;
+                        while iterations < niterations
+                            && deadline.is_none_or(|deadline| Instant::now() < deadline)
+                        {
+                            tree.iterate(state);
+                            iterations += 1;
+                        }
+                        (iterations, tree.root_stats())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("search thread panicked"))
+                .collect()
+        });
+
+        let mut iterations = 0;
+        let mut merged: HashMap<Action, (u64, f64)> = HashMap::new();
+        for (thread_iterations, stats) in thread_results {
+            iterations += thread_iterations;
+            for (action, visits, total_reward) in stats {
+                let entry = merged.entry(action).or_insert((0, 0.0));
+                entry.0 += visits;
+                entry.1 += total_reward;
+            }
+        }
+        let mut stats: Vec<ActionStats> = merged
+            .into_iter()
+            .map(|(action, (visits, total_reward))| ActionStats {
+                action,
+                visits,
+                mean_reward: total_reward / visits.max(1) as f64,
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            b.visits
+                .cmp(&a.visits)
+                .then(b.mean_reward.total_cmp(&a.mean_reward))
+        });
+        let action = match stats.first() {
+            Some(best) => best.action.clone(),
+            // no time for a single iteration
+            None => {
+                let agent_idx = state.current_player_i as usize;
+                turn_actions(state, agent_idx)[0].clone()
+            }
+        };
+        SearchResult {
+            action,
+            iterations,
+            stats,
+        }
+    }
+}
+
+impl Controller for MctsAI {
+    fn choose_action(&mut self, state: &PandemicGameState, _agent_idx: usize) -> Action {
+        self.search(state).action
+    }
+
+    fn choose_discards(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        ndiscards: usize,
+    ) -> Vec<PlayerCard> {
+        least_useful_cards(state, agent_idx, ndiscards)
+    }
+
+    fn choose_event(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idxs: &[usize],
+        _window: EventWindow,
+    ) -> Option<Decision> {
+        None
+    }
+
+    fn choose_forecast_order(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> Vec<usize> {
+        (0..cards.len()).collect()
+    }
+
+    fn choose_resilient_population_target(
+        &mut self,
+        _state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> CityCard {
+        cards[0]
+    }
+}
@@ -1,14 +1,20 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::io::{self, Write};
 use std::mem;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
 
+use crate::action::{Action, EventAction, MoveAction};
 use crate::agent::{ActionEndState, ActionError, Agent, AgentName, TurnEndState, TurnError};
-use crate::city_graph::{city_diseases, city_graph, CityCard};
+use crate::board::{Board, CityCard, NamedOnBoard};
+use crate::controller::{decide, Controller};
 use crate::game_enums::{Disease, EventCard, GameEnd, PlayerCard};
+use crate::ruleset::{Difficulty, StartingPlayer, STANDARD_INFECTION_RATES};
+use crate::scheduler::{Decision, PendingDecision, TurnPhase, ACTIONS_PER_TURN};
 use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
 fn create_action_err_result(msg: String) -> Result<(), ActionEndState> {
     Result::Err(ActionEndState::Err(ActionError::new(msg)))
@@ -24,38 +30,61 @@ impl PlayerCard {
     pub fn from_event_card(card: EventCard) -> PlayerCard {
         PlayerCard::EventCard(card)
     }
-    pub fn from_str(s: &str) -> Result<PlayerCard, ()> {
+    pub fn to_str(self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl NamedOnBoard for PlayerCard {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerCard::CityCard(city) => write!(f, "{}", board.named(city)),
+            PlayerCard::EventCard(event) => write!(f, "{:?}", event),
+            PlayerCard::Epidemic => write!(f, "Epidemic"),
+        }
+    }
+}
+
+impl Board {
+    // a player card by name, city names being the ones on this board
+    pub fn parse_player_card(&self, s: &str) -> Result<PlayerCard, String> {
         match s.parse::<EventCard>() {
             Result::Ok(event_card) => Result::Ok(PlayerCard::EventCard(event_card)),
-            Result::Err(_) => match s.parse::<CityCard>() {
-                Result::Ok(city_card) => Result::Ok(PlayerCard::CityCard(city_card)),
-                Result::Err(_) => match s {
+            Result::Err(_) => match self.find(s) {
+                Some(city_card) => Result::Ok(PlayerCard::CityCard(city_card)),
+                None => match s {
                     "Epidemic" => Ok(PlayerCard::Epidemic),
-                    _ => Result::Err(()),
+                    _ => Result::Err(format!("unknown player card {}", s)),
                 },
             },
         }
     }
-    pub fn to_str(self) -> String {
-        format!("{:?}", self)
-    }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PandemicGameConfig {
     pub nplayers: i32,
     pub nepidemics: i32,
     pub ncards_to_draw: u32,
-    pub max_disease_cubes_per_color: u32,
+    // the supply of each disease, indexed by Disease::index()
+    pub max_disease_cubes: Vec<u32>,
     pub max_outbreaks: u32,
     pub infection_rates: Vec<usize>,
     pub starting_cards_per_hand: i32,
-    pub city_graph: HashMap<CityCard, Vec<CityCard>>,
-    pub city_diseases: HashMap<CityCard, Disease>,
+    pub board: Board,
+    // the board's, all of which need curing to win
     pub ndiseases: u32,
     pub events: Vec<EventCard>,
-    pub testing: bool,
-    pub interactive: bool,
+    pub hand_limit: usize,
+    pub max_research_stations: usize,
+    // every shuffle and random choice in a game is derived from this seed
+    pub seed: u64,
     pub do_events: bool,
+    // check invariants after every action and infection step, and panic on the first violation
+    pub debug: bool,
+    pub difficulty: Difficulty,
+    pub starting_player: StartingPlayer,
+    pub roles: Vec<AgentName>,
 }
 
 // actions
@@ -76,117 +105,543 @@ impl fmt::Display for PandemicGameConfig {
     }
 }
 impl PandemicGameConfig {
-    pub fn new(
-        nplayers: i32,
-        nepidemics: Option<i32>,
-        ncards_to_draw: Option<u32>,
-        max_disease_cubes_per_color: Option<u32>,
-        max_outbreaks: Option<u32>,
-        infection_rates: Option<Vec<usize>>,
-        testing: Option<bool>,
-        interactive: Option<bool>,
-        do_events: Option<bool>,
-    ) -> Self {
+    pub fn builder() -> PandemicGameConfigBuilder {
+        PandemicGameConfigBuilder::default()
+    }
+
+    // player cards left in the deck once hands are dealt, before epidemics are shuffled in
+    pub fn player_deck_size(&self) -> usize {
+        (self.board.ncities() + self.events.len())
+            .saturating_sub(self.nplayers as usize * self.starting_cards_per_hand as usize)
+    }
+}
+
+// the most cubes of one color setup can place: three cities each at 3, 2 and 1 cubes
+const SETUP_CUBES_PER_COLOR: u32 = 18;
+
+// Why PandemicGameConfigBuilder::build refused a config
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ConfigError {
+    PlayerCount(i32),
+    // each epidemic needs a non-empty pile of the deck left after dealing hands
+    EpidemicCount { nepidemics: i32, deck_size: usize },
+    // the track needs a rate for the start and one after each epidemic
+    InfectionRates { nrates: usize, nepidemics: i32 },
+    CubeLimit(u32),
+    // per disease supplies given for a different number of diseases than the board has
+    CubeSupplies { nsupplies: usize, ndiseases: usize },
+    // a difficulty preset and a setting it fixes were both given
+    PresetOverride(Difficulty, &'static str),
+    UnknownEvent(String),
+    DuplicateEvent(EventCard),
+    RolePool { nroles: usize, nplayers: i32 },
+    DuplicateRole(AgentName),
+    StartingPlayer(u32),
+    // a count that can't be zero
+    Zero(&'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::PlayerCount(n) => {
+                write!(f, "only 2-4 players supported, {} players requested", n)
+            }
+            ConfigError::EpidemicCount { nepidemics, .. } if *nepidemics < 0 => {
+                write!(f, "{} epidemics, can't be negative", nepidemics)
+            }
+            ConfigError::EpidemicCount {
+                nepidemics,
+                deck_size,
+            } => write!(
+                f,
+                "{} epidemics but only {} cards in the player deck after dealing",
+                nepidemics, deck_size
+            ),
+            ConfigError::InfectionRates { nrates, nepidemics } => write!(
+                f,
+                "{} epidemics need at least {} infection rates, got {}",
+                nepidemics,
+                nepidemics + 1,
+                nrates
+            ),
+            ConfigError::CubeLimit(n) => write!(
+                f,
+                "{} cubes per color, setup can need {}",
+                n, SETUP_CUBES_PER_COLOR
+            ),
+            ConfigError::CubeSupplies {
+                nsupplies,
+                ndiseases,
+            } => write!(
+                f,
+                "{} cube supplies for {} diseases",
+                nsupplies, ndiseases
+            ),
+            ConfigError::PresetOverride(difficulty, setting) => write!(
+                f,
+                "{:?} difficulty sets {}, use Custom to change it",
+                difficulty, setting
+            ),
+            ConfigError::UnknownEvent(name) => write!(f, "unknown event {}", name),
+            ConfigError::DuplicateEvent(event) => write!(f, "{:?} listed twice", event),
+            ConfigError::RolePool { nroles, nplayers } => write!(
+                f,
+                "{} players but only {} roles to deal from",
+                nplayers, nroles
+            ),
+            ConfigError::DuplicateRole(role) => write!(f, "{:?} listed twice", role),
+            ConfigError::StartingPlayer(seat) => {
+                write!(f, "starting player {} isn't at the table", seat)
+            }
+            ConfigError::Zero(setting) => write!(f, "{} must be at least 1", setting),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Named settings for a PandemicGameConfig, anything left unset gets the standard rules
+#[derive(Debug, Default, Clone)]
+pub struct PandemicGameConfigBuilder {
+    nplayers: Option<i32>,
+    board: Option<Board>,
+    difficulty: Option<Difficulty>,
+    nepidemics: Option<i32>,
+    ncards_to_draw: Option<u32>,
+    // whichever of these was set last, the board's supplies if neither
+    max_disease_cubes_per_color: Option<u32>,
+    max_disease_cubes: Option<Vec<u32>>,
+    max_outbreaks: Option<u32>,
+    infection_rates: Option<Vec<usize>>,
+    hand_limit: Option<usize>,
+    max_research_stations: Option<usize>,
+    // by name, so unknown names are reported by build
+    events: Option<Vec<String>>,
+    starting_player: Option<StartingPlayer>,
+    roles: Option<Vec<AgentName>>,
+    do_events: Option<bool>,
+    debug: Option<bool>,
+    seed: Option<u64>,
+}
+
+impl PandemicGameConfigBuilder {
+    pub fn nplayers(mut self, nplayers: i32) -> Self {
+        self.nplayers = Some(nplayers);
+        self
+    }
+
+    pub fn board(mut self, board: Board) -> Self {
+        self.board = Some(board);
+        self
+    }
+
+    // fixes nepidemics and the infection rates, unless it's Custom
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    pub fn nepidemics(mut self, nepidemics: i32) -> Self {
+        self.nepidemics = Some(nepidemics);
+        self
+    }
+
+    pub fn ncards_to_draw(mut self, ncards_to_draw: u32) -> Self {
+        self.ncards_to_draw = Some(ncards_to_draw);
+        self
+    }
+
+    // the same supply for every disease
+    pub fn max_disease_cubes_per_color(mut self, max_disease_cubes_per_color: u32) -> Self {
+        self.max_disease_cubes_per_color = Some(max_disease_cubes_per_color);
+        self.max_disease_cubes = None;
+        self
+    }
+
+    // a supply for each of the board's diseases, in the order of their ids
+    pub fn max_disease_cubes(mut self, max_disease_cubes: &[u32]) -> Self {
+        self.max_disease_cubes = Some(max_disease_cubes.to_vec());
+        self.max_disease_cubes_per_color = None;
+        self
+    }
+
+    pub fn max_outbreaks(mut self, max_outbreaks: u32) -> Self {
+        self.max_outbreaks = Some(max_outbreaks);
+        self
+    }
+
+    pub fn infection_rates(mut self, infection_rates: Vec<usize>) -> Self {
+        self.infection_rates = Some(infection_rates);
+        self
+    }
+
+    pub fn hand_limit(mut self, hand_limit: usize) -> Self {
+        self.hand_limit = Some(hand_limit);
+        self
+    }
+
+    pub fn max_research_stations(mut self, max_research_stations: usize) -> Self {
+        self.max_research_stations = Some(max_research_stations);
+        self
+    }
+
+    pub fn events(mut self, events: &[EventCard]) -> Self {
+        self.events = Some(events.iter().map(|e| format!("{:?}", e)).collect());
+        self
+    }
+
+    pub fn event_names<S: AsRef<str>>(mut self, names: &[S]) -> Self {
+        self.events = Some(names.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    pub fn starting_player(mut self, starting_player: StartingPlayer) -> Self {
+        self.starting_player = Some(starting_player);
+        self
+    }
+
+    // roles are dealt from this pool instead of all of them
+    pub fn roles(mut self, roles: &[AgentName]) -> Self {
+        self.roles = Some(roles.to_vec());
+        self
+    }
+
+    // false plays without event cards, whichever events are set
+    pub fn do_events(mut self, do_events: bool) -> Self {
+        self.do_events = Some(do_events);
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = Some(debug);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(&self) -> Result<PandemicGameConfig, ConfigError> {
+        let nplayers = self.nplayers.unwrap_or(4);
         let starting_cards_per_hand = match nplayers {
             2 => 4,
             3 => 3,
             4 => 2,
-            other => panic!("Only 2-4 players supported, {other} players requests"),
+            other => return Result::Err(ConfigError::PlayerCount(other)),
         };
 
-        let nepidemics = nepidemics.unwrap_or(4);
-        let infection_rates = infection_rates.unwrap_or(vec![2, 2, 2, 3, 3, 4, 4]);
+        let events = match &self.events {
+            None => EventCard::iter().collect(),
+            Some(names) => {
+                let mut events: Vec<EventCard> = Vec::new();
+                for name in names {
+                    let event: EventCard = name
+                        .parse()
+                        .map_err(|_| ConfigError::UnknownEvent(name.clone()))?;
+                    if events.contains(&event) {
+                        return Result::Err(ConfigError::DuplicateEvent(event));
+                    }
+                    events.push(event);
+                }
+                events
+            }
+        };
+        // without events they are left out of the player deck, not dealt as dead cards
+        let do_events = self.do_events.unwrap_or(true);
+        let events = if do_events { events } else { Vec::new() };
+
+        let infection_rates = self
+            .infection_rates
+            .clone()
+            .unwrap_or(STANDARD_INFECTION_RATES.to_vec());
+        let (difficulty, nepidemics) = match (self.difficulty, self.nepidemics) {
+            (None, nepidemics) => {
+                let nepidemics = nepidemics.unwrap_or(4);
+                (Difficulty::of(nepidemics, &infection_rates), nepidemics)
+            }
+            (Some(Difficulty::Custom), nepidemics) => (Difficulty::Custom, nepidemics.unwrap_or(4)),
+            (Some(difficulty), nepidemics) => {
+                let preset = difficulty.nepidemics().unwrap();
+                if nepidemics.is_some_and(|n| n != preset) {
+                    return Result::Err(ConfigError::PresetOverride(difficulty, "nepidemics"));
+                }
+                if infection_rates != STANDARD_INFECTION_RATES {
+                    return Result::Err(ConfigError::PresetOverride(difficulty, "infection_rates"));
+                }
+                (difficulty, preset)
+            }
+        };
+        let board = self.board.clone().unwrap_or_default();
+        let deck_size = (board.ncities() + events.len())
+            .saturating_sub(nplayers as usize * starting_cards_per_hand as usize);
+        if nepidemics < 0 || nepidemics as usize > deck_size {
+            return Result::Err(ConfigError::EpidemicCount {
+                nepidemics,
+                deck_size,
+            });
+        }
         if (infection_rates.len() as i32) < nepidemics + 1 {
-            // TODO: return a Result from this fn instead of panicking
-            panic!("Infection rates must be >= self.next_player + 1")
+            return Result::Err(ConfigError::InfectionRates {
+                nrates: infection_rates.len(),
+                nepidemics,
+            });
+        }
+
+        let ndiseases = board.ndiseases();
+        let max_disease_cubes = match (&self.max_disease_cubes, self.max_disease_cubes_per_color) {
+            (Some(supplies), _) => supplies.clone(),
+            (None, Some(n)) => vec![n; ndiseases],
+            (None, None) => board.diseases().map(|d| board.disease_cubes(d)).collect(),
+        };
+        if max_disease_cubes.len() != ndiseases {
+            return Result::Err(ConfigError::CubeSupplies {
+                nsupplies: max_disease_cubes.len(),
+                ndiseases,
+            });
+        }
+        if let Some(n) = max_disease_cubes
+            .iter()
+            .find(|n| **n < SETUP_CUBES_PER_COLOR)
+        {
+            return Result::Err(ConfigError::CubeLimit(*n));
+        }
+        let ncards_to_draw = self.ncards_to_draw.unwrap_or(2);
+        let max_outbreaks = self.max_outbreaks.unwrap_or(8);
+        let hand_limit = self.hand_limit.unwrap_or(7);
+        let max_research_stations = self.max_research_stations.unwrap_or(6);
+        for (setting, value) in [
+            ("ncards_to_draw", ncards_to_draw as usize),
+            ("max_outbreaks", max_outbreaks as usize),
+            ("hand_limit", hand_limit),
+            ("max_research_stations", max_research_stations),
+        ] {
+            if value == 0 {
+                return Result::Err(ConfigError::Zero(setting));
+            }
         }
 
-        let mut events: Vec<EventCard> = Vec::new();
-        for e in EventCard::iter() {
-            events.push(e);
+        let roles = match &self.roles {
+            None => AgentName::iter().collect(),
+            Some(roles) => {
+                for (i, role) in roles.iter().enumerate() {
+                    if roles[..i].contains(role) {
+                        return Result::Err(ConfigError::DuplicateRole(*role));
+                    }
+                }
+                roles.clone()
+            }
+        };
+        if roles.len() < nplayers as usize {
+            return Result::Err(ConfigError::RolePool {
+                nroles: roles.len(),
+                nplayers,
+            });
+        }
+        let starting_player = self.starting_player.unwrap_or(StartingPlayer::Random);
+        if let StartingPlayer::Fixed(seat) = starting_player {
+            if seat >= nplayers as u32 {
+                return Result::Err(ConfigError::StartingPlayer(seat));
+            }
         }
 
-        PandemicGameConfig {
+        let seed = self.seed.unwrap_or_else(|| thread_rng().gen());
+
+        Result::Ok(PandemicGameConfig {
             nplayers,
             nepidemics,
-            ncards_to_draw: ncards_to_draw.unwrap_or(2),
-            max_disease_cubes_per_color: max_disease_cubes_per_color.unwrap_or(24),
-            max_outbreaks: max_outbreaks.unwrap_or(8),
+            ncards_to_draw,
+            max_disease_cubes,
+            max_outbreaks,
             infection_rates,
             starting_cards_per_hand,
-            city_graph: city_graph(),
+            board,
             events,
-            testing: testing.unwrap_or(false),
-            city_diseases: city_diseases(),
-            ndiseases: Disease::iter().count() as u32,
-            interactive: interactive.unwrap_or(true),
-            do_events: do_events.unwrap_or(true),
+            hand_limit,
+            max_research_stations,
+            seed,
+            ndiseases: ndiseases as u32,
+            do_events,
+            debug: self.debug.unwrap_or(false),
+            difficulty,
+            starting_player,
+            roles,
+        })
+    }
+}
+
+// Why a cube wasn't placed
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum CubeBlocker {
+    Eradicated,
+    Medic,
+    Quarantine,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub struct Outbreak {
+    pub city: CityCard,
+    pub disease: Disease,
+    // index in InfectionReport::outbreaks of the outbreak that spread to city, None if city was
+    // infected directly
+    pub parent: Option<usize>,
+}
+
+// What placing cubes from one or more infection cards did. Outbreaks form a tree per card, listed
+// in the order they happened
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct InfectionReport {
+    // cubes placed in each city, in the order the cities got their first cube
+    pub cubes_placed: Vec<(CityCard, Disease, u32)>,
+    // one entry per cube
+    pub cubes_blocked: Vec<(CityCard, Disease, CubeBlocker)>,
+    pub outbreaks: Vec<Outbreak>,
+    // the game's outbreak counter afterwards
+    pub outbreak_count: u32,
+}
+
+impl InfectionReport {
+    fn add_cube(&mut self, city: CityCard, disease: Disease) {
+        match self
+            .cubes_placed
+            .iter_mut()
+            .find(|(c, d, _)| *c == city && *d == disease)
+        {
+            Some((_, _, ncubes)) => *ncubes += 1,
+            None => self.cubes_placed.push((city, disease, 1)),
         }
     }
+
+    // outbreaks caused by the outbreak at outbreaks[outbreak_i]
+    pub fn children(&self, outbreak_i: usize) -> Vec<usize> {
+        (0..self.outbreaks.len())
+            .filter(|i| self.outbreaks[*i].parent == Some(outbreak_i))
+            .collect()
+    }
 }
-pub struct PandemicGameState<'a> {
-    pub cur_city_diseases: HashMap<CityCard, HashMap<Disease, u32>>,
-    pub player_locations: HashMap<AgentName, CityCard>,
-    pub research_stations: HashSet<CityCard>,
-    pub total_cubes_on_board_per_disease: HashMap<Disease, u32>,
+
+// What an epidemic did, in the order the rules resolve it
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct EpidemicReport {
+    // after the increase
+    pub infection_rate: usize,
+    // the bottom card of the infection deck
+    pub city: CityCard,
+    pub disease: Disease,
+    // cubes placed in city, 0 if the disease is eradicated or city is protected
+    pub cubes_added: u32,
+    // everything the 3 cubes did, including outbreaks
+    pub infection: InfectionReport,
+    // infection discard put back on top of the deck, including city unless Resilient Population
+    // removed it first. Empty until the intensify step
+    pub intensified: Vec<CityCard>,
+}
+
+// Everything that changes during a game is owned and stored compactly, and the static config is
+// shared, so states are cheap to clone for simulation. See snapshot for saving one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PandemicGameState {
+    // cubes of each disease in each city, at CityCard::index() * ndiseases + Disease::index()
+    pub cur_city_diseases: Vec<u8>,
+    // indexed by agent_idx, like player_hands
+    pub player_locations: Vec<CityCard>,
+    pub research_stations: Vec<CityCard>,
+    // indexed by Disease::index(), like cured_diseases
+    pub total_cubes_on_board_per_disease: Vec<u32>,
     pub infection_deck: Vec<CityCard>,
     pub infection_discard: Vec<CityCard>,
+    // by Resilient Population
+    pub infection_cards_removed: Vec<CityCard>,
+    // Sizes of the runs of cards on top of the infection deck whose order players know something
+    // about, top first. Each epidemic puts a run of the cards it intensified on top, and each card
+    // ordered by Forecast is a run of its own. The cards below them are in unknown order
+    pub known_infection_runs: Vec<usize>,
     pub player_deck: Vec<PlayerCard>,
     pub player_discard: Vec<PlayerCard>,
-    pub player_hands: HashMap<AgentName, HashSet<PlayerCard>>,
-    pub cured_diseases: HashSet<Disease>,
+    pub player_hands: Vec<Vec<PlayerCard>>,
+    pub cured_diseases: Vec<bool>,
     pub infection_rate_i: usize,
     pub outbreaks: u32,
     pub forecasted_infection_deck: Vec<CityCard>,
     pub forecast_order: Vec<usize>,
     pub skip_next_infect_cities: bool,
+    pub contingency_planner_event_card: Option<EventCard>,
     pub players: Vec<AgentName>,
     pub current_player_i: u32,
     pub did_ops_move: bool,
-    pub agents: Vec<Agent<'a>>,
-    rng: rand::rngs::ThreadRng,
-    pub config: PandemicGameConfig,
+    pub agents: Vec<Agent>,
+    pub phase: TurnPhase,
+    pub actions_left: u32,
+    pub event_window_open: bool,
+    // Forecast or Resilient Population played through the scheduler, waiting on its arguments
+    pub pending_event: Option<(usize, EventCard)>,
+    // every epidemic so far, oldest first
+    pub epidemic_reports: Vec<EpidemicReport>,
+    // the last infect step, empty if One Quiet Night skipped it
+    pub infection_report: InfectionReport,
+    // infect steps so far, skipped ones included, so each infection_report can be told apart
+    pub infection_steps: u32,
+    rng: ChaCha8Rng,
+    pub config: Arc<PandemicGameConfig>,
 }
 
-impl<'a> fmt::Display for PandemicGameState<'a> {
+// states are forked across threads during simulation
+const _: () = {
+    fn assert_clone_send_sync<T: Clone + Send + Sync>() {}
+    let _ = assert_clone_send_sync::<PandemicGameState>;
+};
+
+impl fmt::Display for PandemicGameState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "PandemicGameState: \n\
                board: {city_diseases:#?}",
-            city_diseases = self.cur_city_diseases
+            city_diseases = self.board_cubes()
         )
     }
 }
 
-impl<'a> PandemicGameState<'a> {
+impl PandemicGameState {
     pub fn new(config: PandemicGameConfig) -> Self {
+        let cities: Vec<CityCard> = config.board.cities().collect();
+        let ndiseases = config.board.ndiseases();
         let mut state = PandemicGameState {
-            cur_city_diseases: HashMap::new(),
-            player_locations: HashMap::new(),
-            research_stations: HashSet::new(),
-            total_cubes_on_board_per_disease: HashMap::new(),
-            infection_deck: config.city_graph.keys().map(|k| *k).collect(),
+            cur_city_diseases: vec![0; config.board.ncities() * ndiseases],
+            player_locations: Vec::new(), // initialize in ::init_board()
+            research_stations: Vec::new(),
+            total_cubes_on_board_per_disease: vec![0; ndiseases],
+            infection_deck: cities,
             infection_discard: Vec::new(),
+            infection_cards_removed: Vec::new(),
+            known_infection_runs: Vec::new(),
             player_deck: Vec::new(), // initialize in ::initialize()
             player_discard: Vec::new(),
-            player_hands: HashMap::new(), // initialize in ::initialize()
-            // map of disease colors to boolean indicating whether the disease is also eradicated
-            cured_diseases: HashSet::new(),
+            player_hands: Vec::new(), // initialize in ::initialize()
+            cured_diseases: vec![false; ndiseases],
             infection_rate_i: 0,
             outbreaks: 0,
             forecasted_infection_deck: Vec::new(),
             forecast_order: Vec::new(),
             skip_next_infect_cities: false,
+            contingency_planner_event_card: None,
             players: Vec::new(), // initialize in ::initialize()
             current_player_i: 0,
             did_ops_move: false,
-            rng: thread_rng(),
+            phase: TurnPhase::Actions,
+            actions_left: ACTIONS_PER_TURN,
+            event_window_open: true,
+            pending_event: None,
+            epidemic_reports: Vec::new(),
+            infection_report: InfectionReport::default(),
+            infection_steps: 0,
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
             agents: vec![
                 Agent::new(AgentName::Contingency),
                 Agent::new(AgentName::Dispatcher),
             ],
-            config,
+            config: Arc::new(config),
         };
         state.initialize();
         state
@@ -198,19 +653,83 @@ impl<'a> PandemicGameState<'a> {
     }
 
     pub fn is_eradicated(&self, disease: Disease) -> bool {
-        self.total_cubes_on_board_per_disease[&disease] == 0
+        self.is_cured(disease) && self.total_cubes_on_board_per_disease[disease.index()] == 0
+    }
+
+    pub fn is_cured(&self, disease: Disease) -> bool {
+        self.cured_diseases[disease.index()]
+    }
+
+    pub fn ncured_diseases(&self) -> usize {
+        self.cured_diseases.iter().filter(|cured| **cured).count()
+    }
+
+    // cities with any cubes on them, for display
+    pub fn board_cubes(&self) -> HashMap<String, HashMap<String, u32>> {
+        let mut board = HashMap::new();
+        for city in self.config.board.cities() {
+            for disease in self.config.board.diseases() {
+                let ncubes = self.get_n_disease_cubes(city, disease);
+                if ncubes > 0 {
+                    board
+                        .entry(self.city_name(city).to_string())
+                        .or_insert_with(HashMap::new)
+                        .insert(self.disease_name(disease).to_string(), ncubes);
+                }
+            }
+        }
+        board
+    }
+
+    pub fn disease_name(&self, disease: Disease) -> &str {
+        self.config.board.disease_name(disease)
+    }
+
+    pub fn city_name(&self, city: CityCard) -> &str {
+        self.config.board.name(city)
+    }
+
+    pub fn agent_name(&self, agent_idx: usize) -> AgentName {
+        self.agents[agent_idx].agent_type
     }
 
-    pub fn is_cured(self, disease: Disease) -> bool {
-        self.cured_diseases.contains(&disease) || self.is_eradicated(disease)
+    pub fn player_location(&self, agent_idx: usize) -> CityCard {
+        self.player_locations[agent_idx]
+    }
+
+    pub fn player_hand(&self, agent_idx: usize) -> &[PlayerCard] {
+        &self.player_hands[agent_idx]
+    }
+
+    fn remove_from_hand(&mut self, agent_idx: usize, card: PlayerCard) {
+        let hand = &mut self.player_hands[agent_idx];
+        if let Some(i) = hand.iter().position(|c| *c == card) {
+            hand.swap_remove(i);
+        }
+    }
+
+    pub(crate) fn discard_player_card(
+        &mut self,
+        agent_idx: usize,
+        card: PlayerCard,
+    ) -> Result<(), ActionEndState> {
+        if !self.player_hand(agent_idx).contains(&card) {
+            return create_action_err_result(format!(
+                "{} not in hand",
+                self.config.board.named(&card)
+            ));
+        }
+        self.remove_from_hand(agent_idx, card);
+        self.player_discard.push(card);
+        Result::Ok(())
     }
 
     pub fn init_player_hands(&mut self) {
-        let agent_names: Vec<_> = self.agents.iter().map(|a| a.agent_type).collect();
-        for agent in agent_names {
+        self.player_hands = Vec::with_capacity(self.agents.len());
+        for _ in 0..self.agents.len() {
             let n = self.config.starting_cards_per_hand as u32;
             let cards = self.draw_player_cards(n).unwrap();
-            self.player_hands.insert(agent, cards.into_iter().collect());
+            self.player_hands.push(cards);
         }
     }
 
@@ -221,32 +740,26 @@ impl<'a> PandemicGameState<'a> {
         self.select_roles();
 
         self.gen_player_deck();
-        if !self.config.testing {
-            self.shuffle_infection_deck()
-        }
-
-        for d in Disease::iter() {
-            self.total_cubes_on_board_per_disease.insert(d, 0);
-        }
+        self.shuffle_infection_deck();
 
         self.init_board();
 
-        if self.config.testing {
-            self.current_player_i = 0;
-        } else {
-            self.current_player_i = self.rng.gen_range(0..self.agents.len() as u32);
-        }
+        self.current_player_i = match self.config.starting_player {
+            StartingPlayer::Random => self.rng.gen_range(0..self.agents.len() as u32),
+            StartingPlayer::Fixed(seat) => seat,
+        };
     }
 
     pub fn incr_current_player(&mut self) {
         self.current_player_i = (self.current_player_i + 1) % self.agents.len() as u32;
+        self.did_ops_move = false;
     }
 
-    pub fn current_player(&self) -> &'a Agent {
+    pub fn current_player(&self) -> &Agent {
         &self.agents[self.current_player_i as usize]
     }
 
-    pub fn next_player(&self) -> &'a Agent {
+    pub fn next_player(&self) -> &Agent {
         &self.agents[((self.current_player_i + 1) % self.agents.len() as u32) as usize]
     }
 
@@ -270,10 +783,8 @@ impl<'a> PandemicGameState<'a> {
         {
             self.player_deck.push(event);
         }
+        self.player_deck.shuffle(&mut self.rng);
         self.init_player_hands();
-        if !self.config.testing {
-            self.player_deck.shuffle(&mut self.rng);
-        }
 
         if self.config.nepidemics > 0 {
             self.add_epidemic_card_to_player_deck();
@@ -288,15 +799,44 @@ impl<'a> PandemicGameState<'a> {
         self.infection_discard.shuffle(&mut self.rng);
     }
 
-    pub fn init_board(&mut self) {
-        for city in self.config.city_graph.keys() {
-            self.cur_city_diseases.insert(*city, HashMap::new());
-        }
-        self.add_research_station(CityCard::Atlanta);
-        for agent in &self.agents {
-            self.player_locations
-                .insert(agent.agent_type, CityCard::Atlanta);
+    // Reshuffle the cards no player can see and reseed, giving one sample of how the rest of the
+    // game could be drawn. Player cards are only shuffled within what is left of their epidemic
+    // piles, and infection cards within their known runs, so cards ordered by Forecast stay put
+    pub fn sample_hidden_cards(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut end = 0;
+        for pile_size in self.epidemic_pile_sizes(self.config.player_deck_size()) {
+            let start = end;
+            end = (start + pile_size + 1).min(self.player_deck.len());
+            self.player_deck[start..end].shuffle(&mut self.rng);
+        }
+        self.player_deck[end..].shuffle(&mut self.rng);
+        // the top of the deck is the end of the vec
+        let mut end = self.infection_deck.len();
+        for run in self.known_infection_runs.clone() {
+            let start = end - run;
+            self.infection_deck[start..end].shuffle(&mut self.rng);
+            end = start;
+        }
+        self.infection_deck[..end].shuffle(&mut self.rng);
+    }
+
+    // the top n cards of the infection deck were drawn or reordered
+    fn remove_known_infection_runs(&mut self, mut n: usize) {
+        while n > 0 && !self.known_infection_runs.is_empty() {
+            let ntaken = n.min(self.known_infection_runs[0]);
+            self.known_infection_runs[0] -= ntaken;
+            if self.known_infection_runs[0] == 0 {
+                self.known_infection_runs.remove(0);
+            }
+            n -= ntaken;
         }
+    }
+
+    pub fn init_board(&mut self) {
+        let starting_city = self.config.board.starting_city();
+        self.add_research_station(starting_city);
+        self.player_locations = vec![starting_city; self.agents.len()];
         let initial_infection_cards = self.draw_infection_cards(9);
         /*
         # first 3 cities get 3 disease cubes
@@ -305,27 +845,74 @@ impl<'a> PandemicGameState<'a> {
         */
         for (i, ndiseases) in (0..3).rev().enumerate() {
             for city in &initial_infection_cards[i * 3..(i + 1) * 3] {
-                for _ in 0..ndiseases {
-                    let result =
-                        self.add_disease_cube(*city, self.config.city_diseases[city], true);
-                    match result {
-                        Result::Ok(_) => {}
-                        Result::Err(e) => {
-                            panic!("Early GameEnd (shouldn't get here): {:?}", e);
-                        }
+                let result = self.add_disease_cubes(
+                    *city,
+                    self.config.board.disease(*city),
+                    ndiseases,
+                    true,
+                    &mut InfectionReport::default(),
+                );
+                match result {
+                    Result::Ok(_) => {}
+                    Result::Err(e) => {
+                        panic!("Early GameEnd (shouldn't get here): {:?}", e);
                     }
                 }
             }
         }
     }
 
-    pub fn add_disease_cube(
+    // Place ncubes cubes in city from one card. This starts a new outbreak chain, and city
+    // outbreaks at most once however many cubes are left over
+    pub fn add_disease_cubes(
         &mut self,
         city: CityCard,
         disease: Disease,
+        ncubes: u32,
         setup: bool,
+        report: &mut InfectionReport,
     ) -> Result<(), GameEnd> {
-        self._add_disease_cube(city, disease, setup, &mut HashSet::<CityCard>::new())
+        report.outbreak_count = self.outbreaks;
+        let mut prior_outbreaks = HashSet::<CityCard>::new();
+        for _ in 0..ncubes {
+            self._add_disease_cube(city, disease, setup, None, &mut prior_outbreaks, report)?;
+            if prior_outbreaks.contains(&city) {
+                break;
+            }
+        }
+        Result::Ok(())
+    }
+
+    fn agent_location(&self, agent_name: AgentName) -> Option<CityCard> {
+        self.agents
+            .iter()
+            .position(|agent| agent.agent_type == agent_name)
+            .map(|agent_idx| self.player_location(agent_idx))
+    }
+
+    // The Medic keeps cubes of cured diseases out of their city, and the Quarantine Specialist
+    // keeps all cubes out of their city and its neighbors once the game has started. Blocked cubes
+    // can't cause outbreaks either
+    pub fn cube_blocker(
+        &self,
+        city: CityCard,
+        disease: Disease,
+        setup: bool,
+    ) -> Option<CubeBlocker> {
+        if self.is_eradicated(disease) {
+            return Some(CubeBlocker::Eradicated);
+        }
+        if self.is_cured(disease) && self.agent_location(AgentName::Medic) == Some(city) {
+            return Some(CubeBlocker::Medic);
+        }
+        if setup {
+            return None;
+        }
+        let quarantine_city = self.agent_location(AgentName::Quarantine)?;
+        if quarantine_city == city || self.config.board.neighbors(quarantine_city).contains(&city) {
+            return Some(CubeBlocker::Quarantine);
+        }
+        None
     }
 
     fn _add_disease_cube(
@@ -333,50 +920,49 @@ impl<'a> PandemicGameState<'a> {
         city: CityCard,
         disease: Disease,
         setup: bool,
-        _prior_neighbors: &mut HashSet<CityCard>,
+        parent: Option<usize>,
+        prior_outbreaks: &mut HashSet<CityCard>,
+        report: &mut InfectionReport,
     ) -> Result<(), GameEnd> {
-        // todo: medic/quarantine
-        let city_diseases = self
-            .cur_city_diseases
-            .entry(city)
-            .or_insert_with(HashMap::new);
-        let current_cubes = city_diseases.entry(disease).or_insert(0);
-
-        if *current_cubes < 3 {
-            *current_cubes += 1;
-            if *self
-                .total_cubes_on_board_per_disease
-                .get(&disease)
-                .expect("disease not found")
-                < self.config.max_disease_cubes_per_color
-            {
-                let cur_per_color_total = self.total_cubes_on_board_per_disease[&disease];
-                self.total_cubes_on_board_per_disease
-                    .insert(disease, cur_per_color_total + 1);
-            } else {
+        if let Some(blocker) = self.cube_blocker(city, disease, setup) {
+            report.cubes_blocked.push((city, disease, blocker));
+            return Result::Ok(());
+        }
+        let slot = self.cube_slot(city, disease);
+        let current_cubes = self.cur_city_diseases[slot];
+
+        if current_cubes < 3 {
+            let cur_per_color_total = &mut self.total_cubes_on_board_per_disease[disease.index()];
+            if *cur_per_color_total == self.config.max_disease_cubes[disease.index()] {
                 return Result::Err(GameEnd::DiseaseCubeLimit);
             }
+            *cur_per_color_total += 1;
+            self.cur_city_diseases[slot] += 1;
+            report.add_cube(city, disease);
         } else {
-            assert_eq!(*current_cubes, 3);
+            assert_eq!(current_cubes, 3);
+            report.outbreaks.push(Outbreak {
+                city,
+                disease,
+                parent,
+            });
+            let outbreak_i = report.outbreaks.len() - 1;
+            prior_outbreaks.insert(city);
             let result = self.increment_outbreak();
-            match result {
-                Result::Ok(_) => {}
-                Result::Err(e) => {
-                    return Result::Err(e);
-                }
-            }
-            _prior_neighbors.insert(city);
-            for neighbor in self.config.city_graph[&city].clone() {
-                if _prior_neighbors.contains(&neighbor) {
+            report.outbreak_count = self.outbreaks;
+            result?;
+            for neighbor in self.config.board.neighbors(city).to_vec() {
+                if prior_outbreaks.contains(&neighbor) {
                     continue;
                 }
-                let result = self._add_disease_cube(neighbor, disease, setup, _prior_neighbors);
-                match result {
-                    Result::Ok(_) => {}
-                    Result::Err(e) => {
-                        return Result::Err(e);
-                    }
-                }
+                self._add_disease_cube(
+                    neighbor,
+                    disease,
+                    setup,
+                    Some(outbreak_i),
+                    prior_outbreaks,
+                    report,
+                )?;
             }
         }
         Result::Ok(())
@@ -390,116 +976,206 @@ impl<'a> PandemicGameState<'a> {
         Result::Ok(())
     }
 
+    // A new station comes from the supply, or once that is empty, from relocate_from
+    pub fn place_research_station(
+        &mut self,
+        city: CityCard,
+        relocate_from: Option<CityCard>,
+    ) -> Result<(), ActionEndState> {
+        let supply_left = self.research_stations.len() < self.config.max_research_stations;
+        match relocate_from {
+            None if !supply_left => {
+                return create_action_err_result(
+                    "no research stations left, one must be relocated".to_string(),
+                );
+            }
+            Some(_) if supply_left => {
+                return create_action_err_result(
+                    "research stations can only be relocated once none are left".to_string(),
+                );
+            }
+            Some(from_city) if !self.has_research_station(from_city) => {
+                return create_action_err_result(format!(
+                    "{} has no research station to relocate",
+                    self.city_name(from_city)
+                ));
+            }
+            Some(from_city) => self.research_stations.retain(|c| *c != from_city),
+            None => {}
+        }
+        self.add_research_station(city);
+        Result::Ok(())
+    }
+
     pub fn add_research_station(&mut self, city: CityCard) {
-        self.research_stations.insert(city);
+        if !self.research_stations.contains(&city) {
+            self.research_stations.push(city);
+        }
+    }
+
+    // The deck only runs out on small maps, where the discard is shuffled to make a new one
+    fn refill_infection_deck(&mut self) {
+        if self.infection_deck.is_empty() {
+            self.infection_deck = mem::take(&mut self.infection_discard);
+            self.infection_deck.shuffle(&mut self.rng);
+            self.known_infection_runs.clear();
+        }
     }
 
     pub fn draw_infection_cards(&mut self, ncards: usize) -> Vec<CityCard> {
         let mut cards: Vec<CityCard> = Vec::new();
         for _ in 0..ncards {
+            self.refill_infection_deck();
             if self.infection_deck.is_empty() {
                 return cards;
-                // TODO: is this a possible state to get into?
-                //self.infection_deck = self.infection_discard.clone();
-                //self.infection_discard = Vec::new();
-                //self.infection_deck.shuffle(&mut self.rng);
             }
             let card = self.infection_deck.pop().unwrap();
+            self.remove_known_infection_runs(1);
             cards.push(card);
             self.infection_discard.push(card);
         }
         cards
     }
 
-    pub fn add_epidemic_card_to_player_deck(&mut self) {
-        // TODO: refactor/clean up
-        let player_deck_split_sz = self.player_deck.len() / self.config.nepidemics as usize;
-        let mut possible_indices: Vec<usize> = (0..player_deck_split_sz).collect();
-        possible_indices.shuffle(&mut self.rng);
-
-        let randints: Vec<usize> = if self.config.testing {
-            (0..self.config.nepidemics as usize - 1).collect()
-        } else {
-            possible_indices[0..self.config.nepidemics as usize - 1].to_vec()
-        };
-
-        let mut epidemic_indices: Vec<usize> = randints[..self.config.nepidemics as usize - 1]
-            .to_vec()
-            .iter()
-            .map(|i| -> usize { player_deck_split_sz * i + randints[*i] })
-            .collect();
-
-        let last_rand_int = randints[self.config.nepidemics as usize - 2];
-        let last_index_start = player_deck_split_sz * (self.config.nepidemics as usize - 1);
-        let last_index = last_index_start + last_rand_int;
-        epidemic_indices.push(last_index);
+    // cards in each epidemic pile of a deck of deck_size cards, not counting the epidemic, bottom
+    // pile first
+    fn epidemic_pile_sizes(&self, deck_size: usize) -> Vec<usize> {
+        let nepidemics = self.config.nepidemics as usize;
+        (0..nepidemics)
+            .map(|pile_i| deck_size / nepidemics + usize::from(pile_i < deck_size % nepidemics))
+            .collect()
+    }
 
-        for (i, epidemic_loc) in epidemic_indices.iter().enumerate() {
-            self.player_deck
-                .insert(epidemic_loc + i, PlayerCard::Epidemic);
+    // split the deck into nepidemics piles and shuffle an epidemic into each one
+    pub fn add_epidemic_card_to_player_deck(&mut self) {
+        let deck = mem::take(&mut self.player_deck);
+        let mut start = 0;
+        for pile_size in self.epidemic_pile_sizes(deck.len()) {
+            let end = start + pile_size;
+            let mut pile = deck[start..end].to_vec();
+            let epidemic_loc = self.rng.gen_range(0..=pile.len());
+            pile.insert(epidemic_loc, PlayerCard::Epidemic);
+            self.player_deck.extend(pile);
+            start = end;
         }
     }
 
     // how should we initialize agents in init?
     pub fn select_roles(&mut self) {
-        let n_agent_types = mem::variant_count::<AgentName>();
-        let mut role_indices: Vec<usize> = (0..n_agent_types).collect();
-        role_indices.shuffle(&mut self.rng);
-        let agents = (0..self.config.nplayers)
-            .map(|p| -> Agent {
-                let agent_type =
-                    num::FromPrimitive::from_u32(role_indices[p as usize] as u32).unwrap();
-                Agent::new(agent_type)
-            })
+        let mut roles = self.config.roles.clone();
+        roles.shuffle(&mut self.rng);
+        self.agents = roles
+            .into_iter()
+            .take(self.config.nplayers as usize)
+            .map(Agent::new)
             .collect();
-        self.agents = agents;
-    }
-
-    pub fn do_action(&mut self, agent_idx: usize, action_idx: usize) -> Result<(), ActionEndState> {
-        if agent_idx < self.agents.len() {
-            let agent = &mut self.agents[agent_idx];
-            if action_idx < agent.actions.len() {
-                let action_fn = agent.actions[action_idx];
-                return action_fn(self, agent_idx);
-            } else {
-                return Result::Err(ActionEndState::Err(ActionError::new(
-                    "action_idx > agent.actions.len()".to_string(),
-                )));
-            }
-        } else {
-            return Result::Err(ActionEndState::Err(ActionError::new(
-                "agent_idx > agents.len()".to_string(),
-            )));
+    }
+
+    // Validate and execute an action for the current player
+    pub fn apply(&mut self, action: Action) -> Result<(), ActionEndState> {
+        self.do_action(self.current_player_i as usize, action)
+    }
+
+    // in debug mode, stop at the first step that breaks an invariant
+    fn debug_check_invariants(&self, step: &str) {
+        if self.config.debug {
+            let violations = self.check_invariants();
+            assert!(
+                violations.is_empty(),
+                "invariants broken after {}: {:#?}",
+                step,
+                violations
+            );
         }
     }
 
-    pub fn do_event(&mut self, agent_idx: usize, event: EventCard) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        if !self.player_hands[&agent_name].contains(&PlayerCard::EventCard(event))
-            && agent_name != AgentName::Contingency
-        {
-            return Result::Err(ActionEndState::Err(ActionError::new(
-                "player does not have event card".to_string(),
-            )));
+    // ids that don't index into the board are refused before anything else looks at them
+    fn check_on_board(&self, cities: &[CityCard]) -> Result<(), ActionEndState> {
+        match cities.iter().find(|c| !self.config.board.contains(**c)) {
+            Some(city) => create_action_err_result(format!("{:?} is not on the map", city)),
+            None => Result::Ok(()),
         }
-        let result = match event {
-            EventCard::Airlift => self.airlift(agent_idx),
-            EventCard::GovernmentGrant => self.government_grant(agent_idx),
-            EventCard::ResilientPopulation => self.resilient_population(agent_idx),
-            EventCard::Forecast => self.forecast(agent_idx),
-            EventCard::OneQuietNight => self.one_quiet_night(agent_idx),
-        };
+    }
 
-        match result {
-            Result::Err(e) => {
-                return Result::Err(ActionEndState::Err(e));
+    pub fn do_action(&mut self, agent_idx: usize, action: Action) -> Result<(), ActionEndState> {
+        if agent_idx >= self.agents.len() {
+            return create_action_err_result("agent_idx > agents.len()".to_string());
+        }
+        if let Some(disease) = action.disease() {
+            if !self.config.board.has_disease(disease) {
+                return create_action_err_result(format!("{:?} is not on the map", disease));
             }
-            _ => {}
         }
-        if agent_name == AgentName::Contingency {
-            self.contingency_planner_event_card = Option::None;
+        self.check_on_board(&action.cities())?;
+        let step = self.config.board.named(&action).to_string();
+        let result = match action {
+            Action::Drive(city) => self.drive(agent_idx, city),
+            Action::DirectFlight(city) => self.direct_flight(agent_idx, city, agent_idx),
+            Action::CharterFlight(city) => self.charter_flight(agent_idx, city, agent_idx),
+            Action::ShuttleFlight(city) => self.shuttle_flight(agent_idx, city),
+            Action::TreatDisease(disease) => self.treat_disease(agent_idx, disease),
+            Action::BuildResearchStation { relocate_from } => {
+                self.build_research_station(agent_idx, relocate_from)
+            }
+            Action::ShareKnowledge {
+                giving_agent_idx,
+                receiving_agent_idx,
+                city,
+            } => self.share_knowledge(agent_idx, giving_agent_idx, receiving_agent_idx, city),
+            Action::DiscoverCure { disease, cards } => {
+                self.discover_cure(agent_idx, disease, cards)
+            }
+            Action::DispatchMove {
+                agent_idx: other_agent_idx,
+                move_action,
+            } => self.dispatch_move(agent_idx, other_agent_idx, move_action),
+            Action::OperationsMove { city, discard } => {
+                self.operations_move(agent_idx, city, discard)
+            }
+            Action::ContingencyPlan(event) => self.contingency_plan(agent_idx, event),
+            Action::PlayEvent {
+                agent_idx: event_agent_idx,
+                event,
+            } => self.do_event(event_agent_idx, event),
+        };
+        self.debug_check_invariants(&step);
+        result
+    }
+
+    pub fn do_event(&mut self, agent_idx: usize, event: EventAction) -> Result<(), ActionEndState> {
+        if agent_idx >= self.agents.len() {
+            return create_action_err_result("agent_idx > agents.len()".to_string());
+        }
+        self.check_on_board(&event.cities())?;
+        let agent_name = self.agent_name(agent_idx);
+        let card = event.card();
+        let in_hand = self
+            .player_hand(agent_idx)
+            .contains(&PlayerCard::EventCard(card));
+        if !in_hand
+            && !(agent_name == AgentName::Contingency
+                && self.contingency_planner_event_card == Some(card))
+        {
+            return create_action_err_result("player does not have event card".to_string());
+        }
+        match event {
+            EventAction::Airlift {
+                agent_idx: airlifted_agent_idx,
+                city,
+            } => self.airlift(airlifted_agent_idx, city),
+            EventAction::GovernmentGrant {
+                city,
+                relocate_from,
+            } => self.government_grant(city, relocate_from),
+            EventAction::ResilientPopulation(city) => self.resilient_population(city),
+            EventAction::Forecast(order) => self.forecast(order),
+            EventAction::OneQuietNight => self.one_quiet_night(),
+        }?;
+        if in_hand {
+            self.discard_player_card(agent_idx, PlayerCard::EventCard(card))?;
         } else {
-            self.player_hands[&agent_name].remove(&event);
+            // contingency planner's stored event is removed from the game
+            self.contingency_planner_event_card = None;
         }
         Result::Ok(())
     }
@@ -517,10 +1193,15 @@ impl<'a> PandemicGameState<'a> {
         Result::Ok(cards)
     }
 
+    // Play agent_idx's whole turn through the scheduler: actions are played in order whenever the
+    // game asks for one, and everything else the turn needs, like event windows, discards and
+    // forecast orders, is asked of controller. The turn is played on a copy, so the state only
+    // changes if all of it is legal
     pub fn player_turn(
         &mut self,
         agent_idx: usize,
-        actions: Vec<usize>,
+        actions: Vec<Action>,
+        controller: &mut dyn Controller,
     ) -> Result<(), TurnEndState> {
         if agent_idx != self.current_player_i as usize {
             return Result::Err(TurnEndState::TErr(TurnError::new(format!(
@@ -528,417 +1209,366 @@ impl<'a> PandemicGameState<'a> {
                 agent_idx, self.current_player_i,
             ))));
         }
-        if actions.len() != 4 {
-            return Result::Err(TurnEndState::TErr(TurnError::new(
-                "must do 4 actions in a turn".to_string(),
-            )));
-        }
-
-        match self.player_turn_part_1(agent_idx, actions) {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(TurnEndState::Ok(game_end));
-            }
-        };
-        match self.player_turn_part_2(self.agents[agent_idx].agent_type) {
-            Result::Ok(_) => Result::Ok(()),
-            Result::Err(game_end) => Result::Err(TurnEndState::Ok(game_end)),
+        if actions.iter().filter(|a| a.uses_action()).count() != self.actions_left as usize {
+            return Result::Err(TurnEndState::TErr(TurnError::new(format!(
+                "must do {} actions this turn",
+                self.actions_left
+            ))));
         }
-    }
-
-    fn player_turn_part_1(&mut self, agent_idx: usize, actions: Vec<usize>) -> Result<(), GameEnd> {
-        // TODO: make idempotent in case of exceptions on later actions
-        self.did_ops_move = false;
-        for action_idx in actions {
-            let result = self.do_action(agent_idx, action_idx);
-            match result {
-                Result::Ok(_) => {}
-                Result::Err(ActionEndState::Ok(game_end)) => {
-                    return Result::Err(game_end);
-                }
-                Result::Err(ActionEndState::Err(action_error)) => {
-                    panic!("invalid action: {}", action_error.msg)
+        let mut state = self.clone();
+        let mut actions = actions.into_iter();
+        let result = loop {
+            let decision = match state.next_decision() {
+                PendingDecision::GameOver(game_end) => {
+                    break Result::Err(TurnEndState::Ok(game_end))
                 }
+                // the next player's turn has started
+                _ if state.current_player_i as usize != agent_idx => break Result::Ok(()),
+                PendingDecision::ChooseAction { .. } => match actions.next() {
+                    Some(action) => Decision::Action(action),
+                    None => {
+                        return Result::Err(TurnEndState::TErr(TurnError::new(
+                            "ran out of actions".to_string(),
+                        )));
+                    }
+                },
+                _ => decide(controller, &state).expect("game is not over"),
+            };
+            if let Result::Err(action_error) = state.resolve(decision) {
+                return Result::Err(TurnEndState::AErr(action_error));
             }
+        };
+        if result.is_ok() && actions.next().is_some() {
+            return Result::Err(TurnEndState::TErr(TurnError::new(
+                "actions left over after the turn ended".to_string(),
+            )));
         }
-        Result::Ok(())
+        *self = state;
+        result
     }
-    pub fn player_turn_part_2(&mut self, agent: AgentName) -> Result<(), GameEnd> {
-        // TODO: if multiple cards in a row are not epidemic, just do discard once instead of each time
-        let new_cards = match self.draw_player_cards(self.config.ncards_to_draw.clone()) {
-            Result::Ok(cards) => cards,
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
-            }
-        };
 
-        for card in new_cards {
-            match self.maybe_do_event() {
-                Result::Ok(_) => {}
-                Result::Err(game_end) => {
-                    return Result::Err(game_end);
-                }
-            };
-            if card == PlayerCard::Epidemic {
-                match self.do_epidemic() {
-                    Result::Ok(_) => {}
-                    Result::Err(game_end) => {
-                        return Result::Err(game_end);
-                    }
-                };
-            } else {
-                self.player_hands
-                    .entry(agent)
-                    .or_insert_with(HashSet::new)
-                    .insert(card);
-                if let Some(cards) = self.player_hands.get_mut(&agent) {
-                    cards.insert(card);
-                }
-                if self.player_hands.get_mut(&agent).unwrap().len() > 7 {
-                    let discard: Vec<PlayerCard>;
-                    if self.config.interactive {
-                        discard = self.choose_cards_to_discard_interactive(agent);
-                    } else {
-                        panic!("policy discard not implemented");
-                        // discard = self.choose_cards_to_discard_policy(&agent);
-                    }
-                    for c in &discard {
-                        self.player_hands.get_mut(&agent).unwrap().remove(c);
-                    }
-                    for card in discard {
-                        self.player_discard.push(card);
-                    }
-                }
-            }
+    // EVENTS
+    pub fn airlift(&mut self, agent_idx: usize, city: CityCard) -> Result<(), ActionEndState> {
+        if agent_idx >= self.agents.len() {
+            return create_action_err_result("agent_idx > agents.len()".to_string());
         }
-        match self.maybe_do_event() {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
-            }
-        };
-        match self.do_infect_step() {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
-            }
-        };
-        match self.maybe_do_event() {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
-            }
+        if self.player_location(agent_idx) == city {
+            return create_action_err_result(format!(
+                "player is already in {}",
+                self.city_name(city)
+            ));
         }
-        self.incr_current_player();
+        self.player_locations[agent_idx] = city;
+        self.remove_cured_if_medic(agent_idx);
         Result::Ok(())
     }
-    pub fn maybe_do_event(&mut self) -> Result<(), GameEnd> {
-        if !self.config.do_events {
-            return Result::Ok(());
+    pub fn government_grant(
+        &mut self,
+        city: CityCard,
+        relocate_from: Option<CityCard>,
+    ) -> Result<(), ActionEndState> {
+        if self.has_research_station(city) {
+            return create_action_err_result(format!(
+                "{} already has a research station",
+                self.city_name(city)
+            ));
         }
-        let agent_types: Vec<AgentName> =
-            self.agents.iter().map(|agent| agent.agent_type).collect();
-        for agent_type in agent_types {
-            let mut input = String::new();
-            if self.config.interactive {
-                loop {
-                    print!(
-                        "Player {:?}: do event? parameters separated by comma",
-                        agent_type
-                    );
-                    io::stdout().flush().unwrap();
-                    io::stdin()
-                        .read_line(&mut input)
-                        .expect("Failed to read line");
-
-                    if input.is_empty() {
-                        continue;
-                    }
-                    let do_event: Result<EventCard, _> = input.parse();
-                    match do_event {
-                        Result::Ok(do_event) => {
-                            if self.player_hands[&agent_type]
-                                .contains(&PlayerCard::EventCard(do_event))
-                            {
-                                let result = self.do_event(do_event);
-                                match result {
-                                    Result::Ok(_) => {}
-                                    Result::Err(game_end) => {
-                                        return Result::Err(game_end);
-                                    }
-                                }
-                            } else {
-                                println!("Invalid event: {:?}", do_event);
-                                continue;
-                            }
-                            break;
-                        }
-                        Result::Err(e) => {
-                            println!("Invalid event: {:?}", e);
-                            continue;
-                        }
-                    }
-                }
-            } else {
-                panic!("policy events not implemented");
-                // do_event = self.do_event_from_policy(agent_type);
+        self.place_research_station(city, relocate_from)
+    }
+    pub fn resilient_population(&mut self, city: CityCard) -> Result<(), ActionEndState> {
+        match self.infection_discard.iter().position(|c| *c == city) {
+            Some(card_idx) => {
+                // removed from the game entirely
+                self.infection_discard.remove(card_idx);
+                self.infection_cards_removed.push(city);
+                Result::Ok(())
             }
+            None => create_action_err_result(format!(
+                "{} not in infection discard",
+                self.city_name(city)
+            )),
         }
-        Result::Ok(())
-    }
-
-    // EVENTS
-    pub fn get_city_input(&self) -> CityCard {
-        // TODO:
-    }
-    pub fn get_card_idx_from_infection_discard_input(&self) -> usize {
-        // TODO
-    }
-    pub fn get_ordered_integers_input(&self, min: usize, max: usize) -> Vec<usize> {
-        // TODO
-    }
-    pub fn airlift(&mut self, agent_idx: usize) -> Result<(), GameEnd> {
-        let city = self.get_city_input();
-        let agent_name = self.agents[agent_idx].agent_type;
-        self.player_locations[&agent_name] = city;
-        Result::Ok(())
-    }
-    pub fn government_grant(&mut self, _agent_idx: usize) -> Result<(), GameEnd> {
-        let city = self.get_city_input();
-        self.add_research_station(city);
-        Result::Ok(())
     }
-    pub fn resilient_population(&mut self, agent_idx: usize) -> Result<(), GameEnd> {
-        // get infection discard card from input
-        let card_idx = self.get_card_idx_from_infection_discard_input();
-        // TODO: is there a more efficient way to remove an element from a vector?
-        self.infection_discard.remove(card_idx);
-        Result::Ok(())
-    }
-    pub fn forecast(&mut self, agent_idx: usize) -> Result<(), GameEnd> {
-        if self.config.interactive {
-            self.forecast_interactive()
-        } else {
-            panic!("policy forecast not implemented");
+    pub fn forecast(&mut self, order: Vec<usize>) -> Result<(), ActionEndState> {
+        if order.is_empty() {
+            return create_action_err_result("forecast order is empty".to_string());
+        }
+        // must be a permutation of the forecasted cards
+        let ncards = self.infection_deck.len().min(6);
+        let mut sorted_order = order.clone();
+        sorted_order.sort_unstable();
+        if !sorted_order.iter().copied().eq(0..ncards) {
+            return create_action_err_result(format!(
+                "forecast order {:?} is not a permutation of 0..{}",
+                order, ncards
+            ));
         }
-        Result::Ok(())
-    }
-    pub fn forecast_interactive(&mut self) {
         self.forecast_part_1();
-        self.forecast_order = self.get_ordered_integers_input(0, 6);
+        self.forecast_order = order;
         self.forecast_part_2();
+        Result::Ok(())
     }
 
     pub fn forecast_part_1(&mut self) {
-        self.forecasted_infection_deck = self.infection_deck[-6:];
+        self.forecasted_infection_deck = self.forecast_cards();
     }
-    pub fn forecast_part_2(&self) {
+    pub fn forecast_part_2(&mut self) {
         let forecast_order_len = self.forecast_order.len();
         let mut new_forecasted_infection_deck: Vec<_> = Vec::with_capacity(forecast_order_len);
         for &i in &self.forecast_order {
             new_forecasted_infection_deck.push(self.forecasted_infection_deck[i]);
         }
         self.forecasted_infection_deck = new_forecasted_infection_deck;
-        
-        let infection_deck_len = self.infection_deck.len();
-        let new_infection_deck = [&self.infection_deck[0..infection_deck_len-6], &self.forecasted_infection_deck[..]].concat();
-        self.infection_deck = new_infection_deck;
 
+        // forecasted cards are listed top first, and the top of the deck is the end of the vec
+        let top = self.infection_deck.len() - self.forecasted_infection_deck.len();
+        self.infection_deck.truncate(top);
+        self.infection_deck
+            .extend(self.forecasted_infection_deck.iter().rev());
+        self.remove_known_infection_runs(forecast_order_len);
+        for _ in 0..forecast_order_len {
+            self.known_infection_runs.insert(0, 1);
+        }
     }
-    
-    pub fn one_quiet_night(&mut self, agent_idx: usize) -> Result<(), GameEnd> {
+
+    pub fn one_quiet_night(&mut self) -> Result<(), ActionEndState> {
         self.skip_next_infect_cities = true;
         Result::Ok(())
     }
     // END EVENTS
 
     pub fn do_infect_step(&mut self) -> Result<(), GameEnd> {
+        self.infection_steps += 1;
+        self.infection_report = InfectionReport {
+            outbreak_count: self.outbreaks,
+            ..InfectionReport::default()
+        };
         if self.skip_next_infect_cities {
             self.skip_next_infect_cities = false;
             return Result::Ok(());
         }
         let cards = self.draw_infection_cards(self.infection_rate());
+        let mut report = mem::take(&mut self.infection_report);
+        let mut result = Result::Ok(());
         for card in cards {
-            let disease = self
-                .config
-                .city_diseases
-                .get(&card)
-                .expect("City card not found in city disease map");
-            if self.is_eradicated(*disease) {
-                continue;
+            let disease = self.config.board.disease(card);
+            result = self.add_disease_cubes(card, disease, 1, false, &mut report);
+            if result.is_err() {
+                break;
             }
-            match self.add_disease_cube(card, *disease, false) {
-                Result::Err(game_end) => {
-                    return Result::Err(game_end);
-                }
-            };
         }
-        Result::Ok(())
+        self.infection_report = report;
+        self.debug_check_invariants("infect step");
+        result
     }
 
-    pub fn do_epidemic(&mut self) -> Result<(), GameEnd> {
+    // Increase and Infect: the bottom card of the infection deck gets 3 cubes, but can only
+    // outbreak once
+    pub fn epidemic_increase_and_infect(&mut self) -> Result<(), GameEnd> {
         // increase
         self.infection_rate_i += 1;
         // infect
-        let card = self
-            .infection_deck
-            .pop()
-            .expect("infection deck should not be empty");
-        self.infection_discard.push(card);
-        // TODO: encapsulate this in a method, along with other locations its used
-        let disease = self
-            .config
-            .city_diseases
-            .get(&card)
-            .expect("City card not found in city disease map");
-        if !self.is_eradicated(*disease) {}
-        match self.maybe_do_event() {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
+        self.refill_infection_deck();
+        assert!(
+            !self.infection_deck.is_empty(),
+            "infection deck should not be empty"
+        );
+        // the bottom card is only in a known run once every card left is
+        if self.known_infection_runs.iter().sum::<usize>() == self.infection_deck.len() {
+            let last = self.known_infection_runs.len() - 1;
+            self.known_infection_runs[last] -= 1;
+            if self.known_infection_runs[last] == 0 {
+                self.known_infection_runs.pop();
             }
-        };
-        // intensify
-        self.shuffle_infection_discard();
-        for _ in 0..self.infection_discard.len() {
-            self.infection_deck
-                .push(self.infection_discard.pop().expect("should not panic"));
         }
-        Result::Ok(())
-    }
-
-    pub fn choose_cards_to_discard_interactive(&self, agent: AgentName) -> Vec<PlayerCard> {
-        let hand = &self.player_hands[&agent];
-        if hand.len() <= 7 {
-            // TODO: return Result. is this a state that can happen?
-            panic!("hand not too big")
+        let city = self.infection_deck.remove(0);
+        self.infection_discard.push(city);
+        let disease = self.config.board.disease(city);
+        let ncubes_before = self.get_n_disease_cubes(city, disease);
+        let mut infection = InfectionReport::default();
+        let result = self.add_disease_cubes(city, disease, 3, false, &mut infection);
+        self.epidemic_reports.push(EpidemicReport {
+            infection_rate: self.infection_rate(),
+            city,
+            disease,
+            cubes_added: self.get_n_disease_cubes(city, disease) - ncubes_before,
+            infection,
+            intensified: Vec::new(),
+        });
+        self.debug_check_invariants("epidemic");
+        result
+    }
+
+    // Intensify: the shuffled infection discard goes on top of the infection deck
+    pub fn epidemic_intensify(&mut self) {
+        self.shuffle_infection_discard();
+        let intensified = mem::take(&mut self.infection_discard);
+        // top of the deck is the end of the vec
+        self.infection_deck.extend(intensified.iter());
+        if !intensified.is_empty() {
+            self.known_infection_runs.insert(0, intensified.len());
         }
-        let hand_strs: Vec<String> = hand.iter().map(|c| c.to_str()).collect();
-        println!("Current hand: {}", hand_strs.join(", "));
-
-        let mut cards_to_discard = vec![];
-        while cards_to_discard.len() != hand.len() - 7
-            && cards_to_discard.iter().all(|card| hand.contains(card))
-        {
-            println!("Enter cards to discard separated by comma");
-            let mut input = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Failed to read line");
-            let input_trimmed = input.trim();
-            let cards: Vec<PlayerCard> = input_trimmed
-                .split(',')
-                .map(|s| PlayerCard::from_str(s).expect("Failed to parse PlayerCard"))
-                .collect();
-            cards_to_discard = cards;
+        if let Some(report) = self.epidemic_reports.last_mut() {
+            report.intensified = intensified;
         }
-        cards_to_discard
     }
 
     // SPECIAL ACTIONS
-    pub fn dispatch_flight(&mut self, agent_idx: usize, other_agent_idx: usize, new_city: CityCard) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
+    pub fn dispatch_flight(
+        &mut self,
+        agent_idx: usize,
+        other_agent_idx: usize,
+        new_city: CityCard,
+    ) -> Result<(), ActionEndState> {
+        let agent_name = self.agent_name(agent_idx);
         if agent_name != AgentName::Dispatcher {
             return create_action_err_result("Only dispatcher can do dispatch flight".to_string());
         }
-        if !self.player_hands[&agent_name].contains(&new_city) {
+        if !self
+            .player_locations
+            .iter()
+            .enumerate()
+            .any(|(i, city)| i != other_agent_idx && *city == new_city)
+        {
             return create_action_err_result(format!(
-                "Player hand does not contain city card {:?}",
-                new_city
+                "No other pawn in city {} to dispatch to",
+                self.city_name(new_city)
             ));
         }
-        let other_agent_name = self.agents[other_agent_idx].agent_type;
-        self.player_locations[&other_agent_name] = new_city;
+        self.player_locations[other_agent_idx] = new_city;
         self.remove_cured_if_medic(other_agent_idx);
         Result::Ok(())
     }
-    // TODO: MoveActionFn is a subset of ActionFn for just drive, direct_flight, charter_flight, shuttle_flight
-    // TODO: how to pass args to actions
-    pub fn dispatch_move(&mut self, agent_idx: usize, other_agent_idx: usize, move_action: MoveActionFn, move_action_args: ActionFnArgs) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
+    pub fn dispatch_move(
+        &mut self,
+        agent_idx: usize,
+        other_agent_idx: usize,
+        move_action: MoveAction,
+    ) -> Result<(), ActionEndState> {
+        let agent_name = self.agent_name(agent_idx);
         if agent_name != AgentName::Dispatcher {
-            return create_action_err_result("Only dispatcher can do dispatch flight".to_string());
+            return create_action_err_result("Only dispatcher can do dispatch move".to_string());
         }
-        match self.do_action(other_agent_idx, move_action, move_action_args) {
-            Result::Ok(_) => {}
-            Result::Err(game_end) => {
-                return Result::Err(game_end);
+        if other_agent_idx >= self.agents.len() {
+            return create_action_err_result("other_agent_idx > agents.len()".to_string());
+        }
+        // the dispatcher pays for flights with their own cards
+        match move_action {
+            MoveAction::Drive(city) => self.drive(other_agent_idx, city),
+            MoveAction::DirectFlight(city) => self.direct_flight(other_agent_idx, city, agent_idx),
+            MoveAction::CharterFlight(city) => {
+                self.charter_flight(other_agent_idx, city, agent_idx)
             }
-        };
-        // TODO: all remove_cur_cured_if_medic can cause game to end
-        // and in general check for all places game can end
-        self.remove_cur_cured_if_medic(other_agent_idx);
-        Result::Ok(())
+            MoveAction::ShuttleFlight(city) => self.shuttle_flight(other_agent_idx, city),
+            MoveAction::DispatchFlight(city) => {
+                self.dispatch_flight(agent_idx, other_agent_idx, city)
+            }
+        }
     }
-    pub fn operations_move(&mut self, agent_idx: usize, new_city: CityCard, card_to_discard: CityCard) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
+    pub fn operations_move(
+        &mut self,
+        agent_idx: usize,
+        new_city: CityCard,
+        card_to_discard: CityCard,
+    ) -> Result<(), ActionEndState> {
+        let agent_name = self.agent_name(agent_idx);
         if agent_name != AgentName::Operations {
-            return create_action_err_result("Only operations agent can do operations move".to_string());
+            return create_action_err_result(
+                "Only operations agent can do operations move".to_string(),
+            );
+        }
+        if self.did_ops_move {
+            return create_action_err_result(
+                "Operations move can only be done once per turn".to_string(),
+            );
         }
-        if !self.has_research_station(self.player_locations[&agent_name]) {
-            return create_action_err_result("Current city does not have research station".to_string());
+        if !self.has_research_station(self.player_location(agent_idx)) {
+            return create_action_err_result(
+                "Current city does not have research station".to_string(),
+            );
         }
-        if !self.player_hands[&agent_name].contains(card_to_discard) {
+        let card = PlayerCard::CityCard(card_to_discard);
+        if !self.player_hand(agent_idx).contains(&card) {
             return create_action_err_result(format!(
-                "Player hand does not contain city card {:?}",
-                card_to_discard
+                "Player hand does not contain city card {}",
+                self.city_name(card_to_discard)
             ));
         }
-        self.player_locations[&agent_name] = new_city;
-        self.player_hands[&agent_name].remove(card_to_discard);
+        self.player_locations[agent_idx] = new_city;
+        self.discard_player_card(agent_idx, card)?;
+        self.remove_cured_if_medic(agent_idx);
+        self.did_ops_move = true;
         Result::Ok(())
     }
-    pub fn contingency_plan(&mut self, _agent_idx: usize, player_discard_event_index: usize) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
+    pub fn contingency_plan(
+        &mut self,
+        agent_idx: usize,
+        event: EventCard,
+    ) -> Result<(), ActionEndState> {
+        let agent_name = self.agent_name(agent_idx);
         if agent_name != AgentName::Contingency {
-            return create_action_err_result("Only contingency agent can do contingency_plan".to_string());
+            return create_action_err_result(
+                "Only contingency agent can do contingency_plan".to_string(),
+            );
         }
-        if player_discard_event_index >= self.player_discard.len() {
-            return create_action_err_result("player_discard_event_index must be within player discard".to_string());
+        if self.contingency_planner_event_card.is_some() {
+            return create_action_err_result(
+                "contingency planner already has an event card to be used".to_string(),
+            );
         }
-        match self.contingency_planner_event_card {
-            Some(_) => {
-                return create_action_err_result("contingency planner already has an event card to be used".to_string());
+        let player_discard_event_index = match self
+            .player_discard
+            .iter()
+            .position(|c| *c == PlayerCard::EventCard(event))
+        {
+            Some(i) => i,
+            None => {
+                return create_action_err_result(format!(
+                    "event card {:?} not in player discard",
+                    event
+                ));
             }
-        }
-        let event_card = self.player_discard[player_discard_event_index];
-        if !self.is_event_card(event_card) {
-            return create_action_err_result("player_discard_event_index must be within player discard".to_string());
-        }
-        // remove from discard, and remove from game entirely
+        };
+        // remove from discard, and remove from game entirely once played
         self.player_discard.remove(player_discard_event_index);
-        self.contingency_planner_event_card = Some(event_card);
+        self.contingency_planner_event_card = Some(event);
         Result::Ok(())
     }
 
-    pub fn get_n_disease_cubes_on_board(&self, agent_idx: usize, disease: &Disease) -> usize {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
-        self.cur_city_diseases[&cur_city][disease]
+    fn cube_slot(&self, city: CityCard, disease: Disease) -> usize {
+        city.index() * self.config.ndiseases as usize + disease.index()
+    }
+
+    pub fn get_n_disease_cubes(&self, city: CityCard, disease: Disease) -> u32 {
+        self.cur_city_diseases[self.cube_slot(city, disease)] as u32
+    }
+
+    fn remove_disease_cubes(&mut self, city: CityCard, disease: Disease, n: u32) {
+        let slot = self.cube_slot(city, disease);
+        self.cur_city_diseases[slot] -= n as u8;
+        self.total_cubes_on_board_per_disease[disease.index()] -= n;
     }
 
     pub fn remove_cured_if_medic(&mut self, agent_idx: usize) {
-        let agent_name = self.agents[agent_idx].agent_type;
-        if agent_name == AgentName::Medic {
-            for disease in self.config.diseases.iter() {
-                if self.is_cured(*disease)
-                    && self.get_n_disease_cubes_on_board(agent_idx, disease) > 0
-                {
-                    self.treat_disease(agent_idx, disease)
+        if self.agent_name(agent_idx) == AgentName::Medic {
+            let city = self.player_location(agent_idx);
+            for disease in self.config.board.diseases() {
+                let ncubes = self.get_n_disease_cubes(city, disease);
+                if self.is_cured(disease) && ncubes > 0 {
+                    self.remove_disease_cubes(city, disease, ncubes);
                 }
             }
         }
     }
     // Normal actions
     pub fn drive(&mut self, agent_idx: usize, new_city: CityCard) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
-        if !self.config.city_graph[&cur_city].contains(&new_city) {
+        let cur_city = self.player_location(agent_idx);
+        if !self.config.board.neighbors(cur_city).contains(&new_city) {
             return create_action_err_result(
                 "Cannot drive to city not connected to current city".to_string(),
             );
         }
-        self.player_locations.insert(agent_name, new_city);
+        self.player_locations[agent_idx] = new_city;
         self.remove_cured_if_medic(agent_idx);
         Result::Ok(())
     }
@@ -947,19 +1577,21 @@ impl<'a> PandemicGameState<'a> {
         agent_idx: usize,
         new_city: CityCard,
     ) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
-        if !self.has_research_station(&new_city) {
+        let cur_city = self.player_location(agent_idx);
+        if !self.has_research_station(new_city) {
             return create_action_err_result(
                 "Cannot shuttle flight to city without research station".to_string(),
             );
         }
-        if !self.has_research_station(&cur_city) {
+        if !self.has_research_station(cur_city) {
             return create_action_err_result(
                 "Cannot shuttle flight from city without research station".to_string(),
             );
         }
-        self.player_locations.insert(&agent_name, &new_city);
+        if cur_city == new_city {
+            return create_action_err_result("Cannot shuttle flight to current city".to_string());
+        }
+        self.player_locations[agent_idx] = new_city;
         self.remove_cured_if_medic(agent_idx);
         Result::Ok(())
     }
@@ -969,28 +1601,32 @@ impl<'a> PandemicGameState<'a> {
         new_city: CityCard,
         agent_to_discard_idx: usize,
     ) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
-        let cur_city_as_player_card = PlayerCard::CityCard(cur_city);
-        if !self.player_hands[&agent_name].contains(&cur_city_as_player_card) {
-            return create_action_err_result(
-                "Cannot charter flight without card for current city".to_string(),
-            );
-        }
-        self.player_locations.insert(&agent_name, &new_city);
-        self.player_discard.push(cur_city_as_player_card);
-        if agent_to_discard_idx > self.agents.len() {
+        if agent_to_discard_idx >= self.agents.len() {
             return create_action_err_result(
                 "Agent trying to discard card for agent that doesn't exist".to_string(),
             );
         }
-        let agent_to_discard = self.agents[agent_to_discard_idx].agent_type;
+        let agent_to_discard = self.agent_name(agent_to_discard_idx);
         if agent_to_discard_idx != agent_idx && agent_to_discard != AgentName::Dispatcher {
             return create_action_err_result(
                 "Agent trying to discard other agent's cards who's not dispatcher".to_string(),
             );
         };
-        self.player_hands[&agent_to_discard].remove(&cur_city_as_player_card);
+        let cur_city = self.player_location(agent_idx);
+        let cur_city_as_player_card = PlayerCard::CityCard(cur_city);
+        if !self
+            .player_hand(agent_to_discard_idx)
+            .contains(&cur_city_as_player_card)
+        {
+            return create_action_err_result(
+                "Cannot charter flight without card for current city".to_string(),
+            );
+        }
+        if cur_city == new_city {
+            return create_action_err_result("Cannot charter flight to current city".to_string());
+        }
+        self.player_locations[agent_idx] = new_city;
+        self.discard_player_card(agent_to_discard_idx, cur_city_as_player_card)?;
         self.remove_cured_if_medic(agent_idx);
         Result::Ok(())
     }
@@ -999,22 +1635,33 @@ impl<'a> PandemicGameState<'a> {
         &mut self,
         agent_idx: usize,
         new_city: CityCard,
+        agent_to_discard_idx: usize,
     ) -> Result<(), ActionEndState> {
-        if !self.player_hands.contains(&new_city) {
+        if agent_to_discard_idx >= self.agents.len() {
             return create_action_err_result(
-                "Cannot direct flight to city without card".to_string(),
+                "Agent trying to discard card for agent that doesn't exist".to_string(),
             );
         }
-        self.player_locations.insert(&agent_name, &new_city);
-        self.player_discard.push(&new_city);
-        let agent_to_discard = self.agents[agent_to_discard_idx].agent_type;
+        let agent_to_discard = self.agent_name(agent_to_discard_idx);
         if agent_to_discard_idx != agent_idx && agent_to_discard != AgentName::Dispatcher {
             return create_action_err_result(
                 "Agent trying to discard other agent's cards who's not dispatcher".to_string(),
             );
         };
-        self.player_hands[&agent_to_discard].remove(&cur_city_as_player_card);
-
+        let new_city_as_player_card = PlayerCard::CityCard(new_city);
+        if !self
+            .player_hand(agent_to_discard_idx)
+            .contains(&new_city_as_player_card)
+        {
+            return create_action_err_result(
+                "Cannot direct flight to city without card".to_string(),
+            );
+        }
+        if self.player_location(agent_idx) == new_city {
+            return create_action_err_result("Cannot direct flight to current city".to_string());
+        }
+        self.player_locations[agent_idx] = new_city;
+        self.discard_player_card(agent_to_discard_idx, new_city_as_player_card)?;
         self.remove_cured_if_medic(agent_idx);
         Result::Ok(())
     }
@@ -1023,8 +1670,8 @@ impl<'a> PandemicGameState<'a> {
         agent_idx: usize,
         disease: Disease,
     ) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
+        let agent_name = self.agent_name(agent_idx);
+        let cur_city = self.player_location(agent_idx);
         self.treat_disease_internal(cur_city, disease, agent_name == AgentName::Medic)
     }
     pub fn treat_disease_internal(
@@ -1033,35 +1680,46 @@ impl<'a> PandemicGameState<'a> {
         disease: Disease,
         is_medic: bool,
     ) -> Result<(), ActionEndState> {
-        let ndiseases = self.get_n_disease_cubes_on_board(city, disease);
+        let ndiseases = self.get_n_disease_cubes(city, disease);
         if ndiseases == 0 {
             return create_action_err_result(
                 "Cannot treat disease in city with no disease cubes".to_string(),
             );
         }
-        let n_to_treat = 1;
-        if is_medic || self.is_cured(disease) {
-            n_to_treat = ndiseases;
-        }
-        self.cur_city_diseases[&city][disease] = ndiseases - n_to_treat;
-        self.total_cubes_on_board_per_disease[disease] -= n_to_treat;
+        let n_to_treat = if is_medic || self.is_cured(disease) {
+            ndiseases
+        } else {
+            1
+        };
+        self.remove_disease_cubes(city, disease, n_to_treat);
         Result::Ok(())
     }
-    pub fn build_research_station(&mut self, _agent_idx: usize) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        let cur_city = self.player_locations[&agent_name];
+    pub fn build_research_station(
+        &mut self,
+        agent_idx: usize,
+        relocate_from: Option<CityCard>,
+    ) -> Result<(), ActionEndState> {
+        let agent_name = self.agent_name(agent_idx);
+        let cur_city = self.player_location(agent_idx);
         if self.has_research_station(cur_city) {
             return create_action_err_result(
                 "Cannot build research station in city with research station".to_string(),
             );
         }
-        if !self.player_hands[agent_name].contains(&cur_city) && agent_name != AgentName::Operations
+        let cur_city_as_player_card = PlayerCard::CityCard(cur_city);
+        if agent_name != AgentName::Operations
+            && !self
+                .player_hand(agent_idx)
+                .contains(&cur_city_as_player_card)
         {
-            return create_action_err_result(format!("do not have matching {:?} card", cur_city));
+            return create_action_err_result(format!(
+                "do not have matching {} card",
+                self.city_name(cur_city)
+            ));
         }
-        self.add_research_station(cur_city);
+        self.place_research_station(cur_city, relocate_from)?;
         if agent_name != AgentName::Operations {
-            self.player_hands[agent_name].remove(&cur_city);
+            self.discard_player_card(agent_idx, cur_city_as_player_card)?;
         }
         Result::Ok(())
     }
@@ -1077,88 +1735,103 @@ impl<'a> PandemicGameState<'a> {
                 "Agent trying to share knowledge for agent that's not themself".to_string(),
             );
         }
-        let giving_agent_name = self.agents[giving_agent_idx].agent_type;
-        let receiving_agent_name = self.agents[receiving_agent_idx].agent_type;
-        let g_player_loc = self.player_locations[&giving_agent_name];
-        let r_player_loc = self.player_locations[&giving_agent_name];
+        if giving_agent_idx >= self.agents.len()
+            || receiving_agent_idx >= self.agents.len()
+            || giving_agent_idx == receiving_agent_idx
+        {
+            return create_action_err_result(
+                "Share knowledge needs two different agents".to_string(),
+            );
+        }
+        let g_player_loc = self.player_location(giving_agent_idx);
+        let r_player_loc = self.player_location(receiving_agent_idx);
         if g_player_loc != r_player_loc {
             return create_action_err_result("Agents not in same city".to_string());
         }
-        let g_player_hand = self.player_hands[&giving_agent_name];
         let city_as_player_card = PlayerCard::CityCard(city);
-        if !g_player_hand.contains(&city_as_player_card) {
+        if !self
+            .player_hand(giving_agent_idx)
+            .contains(&city_as_player_card)
+        {
             return create_action_err_result(format!(
-                "giving agent does not have matching {:?} card",
-                city
+                "giving agent does not have matching {} card",
+                self.city_name(city)
             ));
         }
-        if city != g_player_loc && giving_agent_name != AgentName::Researcher {
+        if city != g_player_loc && self.agent_name(giving_agent_idx) != AgentName::Researcher {
             return create_action_err_result(
                 "giving agent is not Researcher and not in correct city".to_string(),
             );
         }
-        self.player_hands[&giving_agent_name].remove(&city_as_player_card);
-        self.player_hands[&receiving_agent_name].insert(city_as_player_card);
-        if self.player_hands[&receiving_agent_name].len() > 7 {
-            let discard: Vec<PlayerCard>;
-            if self.config.interactive {
-                discard = self.choose_cards_to_discard_interactive(receiving_agent_name)
-            } else {
-                panic!("policy discard not implemented");
-                // discard = self.choose_cards_to_discard_policy(&agent);
-            }
-            for c in discard {
-                self.player_hands[&receiving_agent_name].remove(&c);
-            }
-            self.player_discard.extend(discard)
-        }
+        self.remove_from_hand(giving_agent_idx, city_as_player_card);
+        // going over the hand limit is resolved by the scheduler, see
+        // PendingDecision::ChooseDiscards
+        self.player_hands[receiving_agent_idx].push(city_as_player_card);
         Result::Ok(())
     }
     pub fn discover_cure(
         &mut self,
         agent_idx: usize,
-        disease: &Disease,
+        disease: Disease,
         matching_city_cards: Vec<CityCard>,
     ) -> Result<(), ActionEndState> {
-        let agent_name = self.agents[agent_idx].agent_type;
-        if matching_city_cards.len() != 5
-            || (agent_name == AgentName::Scientist && matching_city_cards.len() == 4)
-        {
+        let agent_name = self.agent_name(agent_idx);
+        let ncards_needed = if agent_name == AgentName::Scientist {
+            4
+        } else {
+            5
+        };
+        if matching_city_cards.len() != ncards_needed {
             return create_action_err_result(
                 "must play exactly 5 city cards (or 4 for scientist)".to_string(),
             );
         }
-        if self.cured_diseases.contains(disease) {
+        if self.is_cured(disease) {
             return create_action_err_result("disease already cured".to_string());
         }
-        let mut player_hand = self.player_hands[&agent_name].clone();
-        let matching_city_cards_set: HashSet<_> = matching_city_cards.into_iter()
-            .map(PlayerCard::CityCard)
+        if !self.has_research_station(self.player_location(agent_idx)) {
+            return create_action_err_result(
+                "must be at a research station to discover a cure".to_string(),
+            );
+        }
+        let matching_city_cards_set: HashSet<PlayerCard> = matching_city_cards
+            .iter()
+            .map(|c| PlayerCard::CityCard(*c))
             .collect();
-        let intersection = player_hand.intersection(&matching_city_cards_set);
-        if (intersection.count()) < matching_city_cards.len() {
+        if matching_city_cards_set.len() != matching_city_cards.len()
+            || !matching_city_cards_set
+                .iter()
+                .all(|c| self.player_hand(agent_idx).contains(c))
+        {
             return create_action_err_result("do not have matching city cards".to_string());
         }
-        let city_diseases: HashSet<Disease> = matching_city_cards
-            .into_iter()
-            .map(|c| self.config.city_diseases[&c])
-            .collect();
-        if city_diseases.len() != 1 {
-            return create_action_err_result(
-                "some of {matching_city_cards} do not have matching disease colors".to_string(),
-            );
+        if matching_city_cards
+            .iter()
+            .any(|c| self.config.board.disease(*c) != disease)
+        {
+            return create_action_err_result(format!(
+                "some of {} do not have matching disease color {}",
+                matching_city_cards
+                    .iter()
+                    .map(|c| self.city_name(*c))
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+                self.disease_name(disease)
+            ));
+        }
+        for card in matching_city_cards_set {
+            self.discard_player_card(agent_idx, card)?;
         }
-        self.player_discard.extend(matching_city_cards.into_iter().map(PlayerCard::CityCard));
-        for city_card in matching_city_cards {
-            player_hand.remove(&PlayerCard::CityCard(city_card));
+        self.cured_diseases[disease.index()] = true;
+        // medic in a city with the newly cured disease removes its cubes
+        for i in 0..self.agents.len() {
+            self.remove_cured_if_medic(i);
         }
-        self.player_hands[&agent_name] = player_hand;
-        self.cured_diseases.insert(*disease);
-        return if self.cured_diseases.len() == self.config.ndiseases as usize {
+        if self.ncured_diseases() == self.config.ndiseases as usize {
             create_action_err_result_win()
         } else {
             Result::Ok(())
-        };
+        }
     }
 }
 
@@ -1208,3 +1881,469 @@ impl<'a> PandemicGameState<'a> {
  * simulated indepdently. The simulation process can literally be the gameplay, running many times
  * to with different random seeds to achieve some degree of sampled expected value
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::RandomController;
+    use crate::scheduler::PendingDecision;
+
+    // no Quarantine Specialist, so nothing keeps cubes off the board
+    fn new_state(seed: u64) -> PandemicGameState {
+        let config = PandemicGameConfig::builder()
+            .seed(seed)
+            .roles(&[
+                AgentName::Medic,
+                AgentName::Scientist,
+                AgentName::Researcher,
+                AgentName::Dispatcher,
+            ])
+            .debug(true)
+            .build()
+            .unwrap();
+        PandemicGameState::new(config)
+    }
+
+    fn set_cubes(state: &mut PandemicGameState, city: CityCard, disease: Disease, n: u8) {
+        let slot = state.cube_slot(city, disease);
+        state.total_cubes_on_board_per_disease[disease.index()] -=
+            state.cur_city_diseases[slot] as u32;
+        state.total_cubes_on_board_per_disease[disease.index()] += n as u32;
+        state.cur_city_diseases[slot] = n;
+    }
+
+    #[test]
+    fn epidemic_infects_bottom_card_with_three_cubes() {
+        let mut state = new_state(1);
+        let bottom = state.infection_deck[0];
+        let disease = state.config.board.disease(bottom);
+        assert_eq!(state.get_n_disease_cubes(bottom, disease), 0);
+
+        state.epidemic_increase_and_infect().unwrap();
+        let report = state.epidemic_reports.last().unwrap();
+        assert_eq!(report.city, bottom);
+        assert_eq!(report.cubes_added, 3);
+        assert!(report.infection.outbreaks.is_empty());
+        assert_eq!(state.get_n_disease_cubes(bottom, disease), 3);
+        assert_eq!(state.infection_rate_i, 1);
+        assert_eq!(report.infection_rate, state.infection_rate());
+        assert_eq!(state.infection_discard.last(), Some(&bottom));
+        assert!(!state.infection_deck.contains(&bottom));
+    }
+
+    #[test]
+    fn epidemic_outbreaks_once_however_many_cubes_are_left() {
+        let mut state = new_state(2);
+        let bottom = state.infection_deck[0];
+        let disease = state.config.board.disease(bottom);
+        set_cubes(&mut state, bottom, disease, 2);
+
+        state.epidemic_increase_and_infect().unwrap();
+        let report = state.epidemic_reports.last().unwrap();
+        assert_eq!(report.cubes_added, 1);
+        assert_eq!(report.infection.outbreaks.len(), 1);
+        assert_eq!(report.infection.outbreaks[0].city, bottom);
+        assert_eq!(state.outbreaks, 1);
+        assert_eq!(state.get_n_disease_cubes(bottom, disease), 3);
+    }
+
+    #[test]
+    fn intensify_puts_shuffled_discard_on_top() {
+        let mut state = new_state(3);
+        state.epidemic_increase_and_infect().unwrap();
+        let mut discard = state.infection_discard.clone();
+        let ndeck = state.infection_deck.len();
+
+        state.epidemic_intensify();
+        let intensified = state.epidemic_reports.last().unwrap().intensified.clone();
+        assert!(state.infection_discard.is_empty());
+        assert_eq!(state.infection_deck.len(), ndeck + intensified.len());
+        // the top of the deck is the end of the vec
+        assert_eq!(&state.infection_deck[ndeck..], &intensified[..]);
+        assert_eq!(state.known_infection_runs, vec![intensified.len()]);
+        let mut sorted = intensified.clone();
+        sorted.sort();
+        discard.sort();
+        assert_eq!(sorted, discard);
+
+        // the next infect step draws from them, last intensified first
+        let drawn = state.draw_infection_cards(2);
+        let n = intensified.len();
+        assert_eq!(drawn, vec![intensified[n - 1], intensified[n - 2]]);
+        assert_eq!(state.known_infection_runs, vec![n - 2]);
+    }
+
+    #[test]
+    fn outbreak_chain_does_not_outbreak_a_city_twice() {
+        let mut state = new_state(4);
+        let a = state.config.board.starting_city();
+        let b = state.config.board.neighbors(a)[0];
+        let disease = state.config.board.disease(a);
+        set_cubes(&mut state, a, disease, 3);
+        set_cubes(&mut state, b, disease, 3);
+
+        let mut report = InfectionReport::default();
+        state
+            .add_disease_cubes(a, disease, 1, false, &mut report)
+            .unwrap();
+        let cities: Vec<CityCard> = report.outbreaks.iter().map(|o| o.city).collect();
+        assert_eq!(cities, vec![a, b]);
+        assert_eq!(report.outbreaks[0].parent, None);
+        assert_eq!(report.outbreaks[1].parent, Some(0));
+        assert_eq!(report.children(0), vec![1]);
+        assert_eq!(state.outbreaks, 2);
+        assert_eq!(report.outbreak_count, 2);
+        assert_eq!(state.get_n_disease_cubes(a, disease), 3);
+        assert_eq!(state.get_n_disease_cubes(b, disease), 3);
+        // neither city got a cube from the other's outbreak
+        assert!(!report
+            .cubes_placed
+            .iter()
+            .any(|(c, _, _)| *c == a || *c == b));
+        assert!(state.check_invariants().is_empty());
+    }
+
+    // a city that is neither city nor one of its neighbors
+    fn far_from(state: &PandemicGameState, city: CityCard) -> CityCard {
+        state
+            .config
+            .board
+            .cities()
+            .find(|c| *c != city && !state.config.board.neighbors(city).contains(c))
+            .unwrap()
+    }
+
+    #[test]
+    fn quarantine_specialist_protects_their_city_and_neighbors_after_setup() {
+        let mut state = new_state(12);
+        state.agents[0] = Agent::new(AgentName::Quarantine);
+        let here = state.player_location(0);
+        let neighbor = state.config.board.neighbors(here)[0];
+        let far = far_from(&state, here);
+        for city in [here, neighbor] {
+            let disease = state.config.board.disease(city);
+            assert_eq!(
+                state.cube_blocker(city, disease, false),
+                Some(CubeBlocker::Quarantine)
+            );
+            assert_eq!(state.cube_blocker(city, disease, true), None);
+        }
+        let disease = state.config.board.disease(far);
+        assert_eq!(state.cube_blocker(far, disease, false), None);
+
+        let disease = state.config.board.disease(neighbor);
+        let ncubes = state.get_n_disease_cubes(neighbor, disease);
+        let mut report = InfectionReport::default();
+        state
+            .add_disease_cubes(neighbor, disease, 1, false, &mut report)
+            .unwrap();
+        assert_eq!(state.get_n_disease_cubes(neighbor, disease), ncubes);
+        assert_eq!(
+            report.cubes_blocked,
+            vec![(neighbor, disease, CubeBlocker::Quarantine)]
+        );
+        assert!(report.cubes_placed.is_empty());
+    }
+
+    #[test]
+    fn medic_only_protects_against_cured_diseases() {
+        let mut state = new_state(13);
+        state.agents[0] = Agent::new(AgentName::Medic);
+        let here = state.player_location(0);
+        let disease = state.config.board.disease(here);
+        let other_disease = state
+            .config
+            .board
+            .diseases()
+            .find(|d| *d != disease)
+            .unwrap();
+        let far = state
+            .config
+            .board
+            .cities()
+            .find(|c| *c != here && state.config.board.disease(*c) == disease)
+            .unwrap();
+        // cured, but not eradicated
+        set_cubes(&mut state, far, disease, 1);
+        assert_eq!(state.cube_blocker(here, disease, false), None);
+
+        state.cured_diseases[disease.index()] = true;
+        assert_eq!(
+            state.cube_blocker(here, disease, false),
+            Some(CubeBlocker::Medic)
+        );
+        assert_eq!(
+            state.cube_blocker(here, disease, true),
+            Some(CubeBlocker::Medic)
+        );
+        assert_eq!(state.cube_blocker(here, other_disease, false), None);
+        assert_eq!(state.cube_blocker(far, disease, false), None);
+    }
+
+    #[test]
+    fn research_stations_are_relocated_once_the_supply_runs_out() {
+        let config = PandemicGameConfig::builder()
+            .seed(14)
+            .max_research_stations(2)
+            .build()
+            .unwrap();
+        let mut state = PandemicGameState::new(config);
+        let start = state.config.board.starting_city();
+        assert_eq!(state.research_stations, vec![start]);
+        let a = state.config.board.neighbors(start)[0];
+        let b = far_from(&state, start);
+
+        assert!(state.place_research_station(a, Some(start)).is_err());
+        state.place_research_station(a, None).unwrap();
+        assert_eq!(state.research_stations, vec![start, a]);
+        assert_eq!(
+            state.research_station_relocations(),
+            vec![Some(start), Some(a)]
+        );
+
+        // the supply is empty
+        assert!(state.place_research_station(b, None).is_err());
+        assert!(state.place_research_station(b, Some(b)).is_err());
+        assert_eq!(state.research_stations, vec![start, a]);
+
+        // through the build action, which uses up the city card
+        let agent_idx = state.current_player_i as usize;
+        state.player_locations[agent_idx] = b;
+        state.player_deck.retain(|c| *c != PlayerCard::CityCard(b));
+        for hand in state.player_hands.iter_mut() {
+            hand.retain(|c| *c != PlayerCard::CityCard(b));
+        }
+        state.player_hands[agent_idx].push(PlayerCard::CityCard(b));
+        assert!(state
+            .apply(Action::BuildResearchStation {
+                relocate_from: None
+            })
+            .is_err());
+        state
+            .apply(Action::BuildResearchStation {
+                relocate_from: Some(start),
+            })
+            .unwrap();
+        assert_eq!(state.research_stations, vec![a, b]);
+        assert!(!state
+            .player_hand(agent_idx)
+            .contains(&PlayerCard::CityCard(b)));
+        assert!(state.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn builder_refuses_bad_configs() {
+        let build = |builder: PandemicGameConfigBuilder| builder.build().unwrap_err();
+        let builder = PandemicGameConfig::builder;
+        assert_eq!(build(builder().nplayers(5)), ConfigError::PlayerCount(5));
+        assert!(matches!(
+            build(builder().nepidemics(-1)),
+            ConfigError::EpidemicCount { nepidemics: -1, .. }
+        ));
+        assert!(matches!(
+            build(builder().nepidemics(100)),
+            ConfigError::EpidemicCount {
+                nepidemics: 100,
+                ..
+            }
+        ));
+        assert_eq!(
+            build(builder().nepidemics(6).infection_rates(vec![2, 2, 3])),
+            ConfigError::InfectionRates {
+                nrates: 3,
+                nepidemics: 6
+            }
+        );
+        assert_eq!(
+            build(builder().max_disease_cubes_per_color(10)),
+            ConfigError::CubeLimit(10)
+        );
+        assert_eq!(
+            build(builder().max_disease_cubes(&[24, 24])),
+            ConfigError::CubeSupplies {
+                nsupplies: 2,
+                ndiseases: 4
+            }
+        );
+        assert_eq!(
+            build(builder().difficulty(Difficulty::Heroic).nepidemics(4)),
+            ConfigError::PresetOverride(Difficulty::Heroic, "nepidemics")
+        );
+        assert_eq!(
+            build(builder().event_names(&["Airlift", "Teleport"])),
+            ConfigError::UnknownEvent("Teleport".to_string())
+        );
+        assert_eq!(
+            build(builder().events(&[EventCard::Airlift, EventCard::Airlift])),
+            ConfigError::DuplicateEvent(EventCard::Airlift)
+        );
+        assert_eq!(
+            build(builder().roles(&[AgentName::Medic, AgentName::Scientist])),
+            ConfigError::RolePool {
+                nroles: 2,
+                nplayers: 4
+            }
+        );
+        assert_eq!(
+            build(builder().roles(&[AgentName::Medic, AgentName::Medic])),
+            ConfigError::DuplicateRole(AgentName::Medic)
+        );
+        assert_eq!(
+            build(builder().starting_player(StartingPlayer::Fixed(4))),
+            ConfigError::StartingPlayer(4)
+        );
+        assert_eq!(
+            build(builder().hand_limit(0)),
+            ConfigError::Zero("hand_limit")
+        );
+    }
+
+    #[test]
+    fn no_event_cards_without_events() {
+        let config = PandemicGameConfig::builder()
+            .seed(5)
+            .do_events(false)
+            .build()
+            .unwrap();
+        let state = PandemicGameState::new(config);
+        assert!(state
+            .player_deck
+            .iter()
+            .chain(state.player_hands.iter().flatten())
+            .all(|c| !matches!(c, PlayerCard::EventCard(_))));
+    }
+
+    #[test]
+    fn actions_naming_cities_off_the_map_are_refused() {
+        let mut state = new_state(6);
+        let agent_idx = state.current_player_i as usize;
+        let here = state.player_location(agent_idx);
+        state.player_hands[agent_idx].push(PlayerCard::CityCard(here));
+        state
+            .player_deck
+            .retain(|c| *c != PlayerCard::CityCard(here));
+        let before = state.to_snapshot_json();
+        for action in [
+            Action::Drive(CityCard(5000)),
+            Action::CharterFlight(CityCard(5000)),
+            Action::PlayEvent {
+                agent_idx,
+                event: EventAction::GovernmentGrant {
+                    city: CityCard(5000),
+                    relocate_from: None,
+                },
+            },
+        ] {
+            assert!(matches!(
+                state.apply(action),
+                Result::Err(ActionEndState::Err(_))
+            ));
+        }
+        assert_eq!(state.to_snapshot_json(), before);
+    }
+
+    #[test]
+    fn actions_naming_diseases_off_the_map_are_refused() {
+        let mut state = new_state(11);
+        let before = state.to_snapshot_json();
+        for action in [
+            Action::TreatDisease(Disease(9)),
+            Action::DiscoverCure {
+                disease: Disease(9),
+                cards: vec![],
+            },
+        ] {
+            assert!(matches!(
+                state.apply(action),
+                Result::Err(ActionEndState::Err(_))
+            ));
+        }
+        assert_eq!(state.to_snapshot_json(), before);
+    }
+
+    #[test]
+    fn discarding_a_card_not_in_hand_is_refused() {
+        let mut state = new_state(7);
+        let card = *state.player_deck.last().unwrap();
+        let ndiscard = state.player_discard.len();
+        assert!(state.discard_player_card(0, card).is_err());
+        assert_eq!(state.player_discard.len(), ndiscard);
+    }
+
+    #[test]
+    fn sampling_keeps_epidemics_in_their_piles() {
+        let mut state = new_state(8);
+        let piles = state.epidemic_pile_sizes(state.config.player_deck_size());
+        let pile_of_epidemics = |state: &PandemicGameState| {
+            let mut pile_of = Vec::new();
+            let mut start = 0;
+            for (pile_i, pile_size) in piles.iter().enumerate() {
+                let end = start + pile_size + 1;
+                for card in &state.player_deck[start..end] {
+                    if *card == PlayerCard::Epidemic {
+                        pile_of.push(pile_i);
+                    }
+                }
+                start = end;
+            }
+            pile_of
+        };
+        assert_eq!(pile_of_epidemics(&state), vec![0, 1, 2, 3]);
+        for seed in 0..20 {
+            state.sample_hidden_cards(seed);
+            assert_eq!(pile_of_epidemics(&state), vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn sampling_keeps_forecast_order() {
+        let mut state = new_state(9);
+        state.forecast(vec![5, 4, 3, 2, 1, 0]).unwrap();
+        assert_eq!(state.known_infection_runs, vec![1; 6]);
+        let ndeck = state.infection_deck.len();
+        let top = state.infection_deck[ndeck - 6..].to_vec();
+        for seed in 0..20 {
+            let mut sampled = state.clone();
+            sampled.sample_hidden_cards(seed);
+            assert_eq!(&sampled.infection_deck[ndeck - 6..], &top[..]);
+        }
+    }
+
+    #[test]
+    fn player_turn_is_all_or_nothing() {
+        let mut state = new_state(10);
+        let agent_idx = state.current_player_i as usize;
+        let here = state.player_location(agent_idx);
+        let neighbor = state.config.board.neighbors(here)[0];
+        let before = state.to_snapshot_json();
+        let result = state.player_turn(
+            agent_idx,
+            vec![
+                Action::Drive(neighbor),
+                Action::Drive(here),
+                Action::Drive(CityCard(5000)),
+                Action::Drive(neighbor),
+            ],
+            &mut RandomController::new(0),
+        );
+        assert!(matches!(result, Result::Err(TurnEndState::AErr(_))));
+        assert_eq!(state.to_snapshot_json(), before);
+
+        let result = state.player_turn(
+            agent_idx,
+            vec![
+                Action::Drive(neighbor),
+                Action::Drive(here),
+                Action::Drive(neighbor),
+                Action::Drive(here),
+            ],
+            &mut RandomController::new(0),
+        );
+        assert!(result.is_ok());
+        assert_ne!(state.current_player_i as usize, agent_idx);
+        assert!(matches!(
+            state.next_decision(),
+            PendingDecision::ChooseAction { .. } | PendingDecision::ChooseEvent { .. }
+        ));
+    }
+}
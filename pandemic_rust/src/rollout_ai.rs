@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::action::{Action, EventAction};
+use crate::board::CityCard;
+use crate::controller::{decide, Controller};
+use crate::game_enums::{Disease, EventCard, GameEnd, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+use crate::scheduler::{Decision, EventWindow, PendingDecision, TurnPhase};
+
+// Heuristic score of a board, lower is better. Weights cured > eradicated > outbreaks > cubes.
+// Cubes in a city that would outbreak count once for every city reachable through a chain of
+// outbreaks, plus one for each neighbor that would get a cube
+pub fn estimate_board_state_score(state: &PandemicGameState) -> f64 {
+    match state.phase {
+        TurnPhase::GameOver(GameEnd::Win) => return f64::NEG_INFINITY,
+        TurnPhase::GameOver(_) => return f64::INFINITY,
+        _ => {}
+    }
+    let config = &state.config;
+    let mut ncube_score = 0.0;
+    for city in config.board.cities() {
+        for disease in config.board.diseases() {
+            let ncubes = state.get_n_disease_cubes(city, disease);
+            if ncubes < 3 {
+                ncube_score += ncubes as f64;
+                continue;
+            }
+            // DFS for outbreaks
+            let mut stack: Vec<CityCard> = vec![city];
+            let mut visited: Vec<CityCard> = Vec::new();
+            while let Some(c) = stack.pop() {
+                visited.push(c);
+                ncube_score += 1.0;
+                for neighbor in config.board.neighbors(c) {
+                    if visited.contains(neighbor) {
+                        continue;
+                    }
+                    if state.get_n_disease_cubes(*neighbor, disease) < 3 {
+                        ncube_score += 1.0;
+                    } else {
+                        stack.push(*neighbor);
+                    }
+                }
+            }
+        }
+    }
+    let ndiseases = config.ndiseases as f64;
+    ncube_score /= config.max_disease_cubes.iter().sum::<u32>() as f64;
+    let outbreak_score = state.outbreaks as f64 / config.max_outbreaks as f64;
+    // fraction of diseases cured, negative because more is better
+    let cured_score = -(state.ncured_diseases() as f64) / ndiseases;
+    // fraction of diseases eradicated, negative because more is better
+    let neradicated = config
+        .board
+        .diseases()
+        .filter(|d| state.is_eradicated(*d))
+        .count();
+    let eradicated_score = -(neradicated as f64) / ndiseases;
+    cured_score * 0.5 + eradicated_score * 0.25 + outbreak_score * 0.2 + ncube_score * 0.05
+}
+
+// The game after agent_idx played actions, through the end of their turn unless the turn was cut
+// short by the game ending
+#[derive(Clone)]
+pub struct SimulatedTurn {
+    pub state: PandemicGameState,
+    pub agent_idx: usize,
+    pub actions: Vec<Action>,
+    pub score: f64,
+}
+
+// Plans every player's turns by greedy lookahead: each action of a turn is scored with
+// estimate_board_state_score and only the best max_estimated_branching_factor partial turns are
+// expanded, and only the best max_simulated_branching_factor full turns are expanded into the
+// next turn. Events are never played inside the lookahead; in an event window each playable
+// event is scored up to the next action and played if it beats passing
+pub struct SingleAgentRolloutAI {
+    pub lookahead_turns: usize,
+    pub max_simulated_branching_factor: usize,
+    pub max_estimated_branching_factor: usize,
+    // chance of swapping the worst kept partial turn for a random pruned one, for exploration
+    pub random_action_prob: f64,
+    rng: ChaCha8Rng,
+    plan: VecDeque<Action>,
+    // set while simulating, so simulated event windows are passed
+    simulating: bool,
+}
+
+impl SingleAgentRolloutAI {
+    pub fn new(lookahead_turns: usize, seed: u64) -> SingleAgentRolloutAI {
+        SingleAgentRolloutAI {
+            lookahead_turns,
+            max_simulated_branching_factor: 5,
+            max_estimated_branching_factor: 10,
+            random_action_prob: 0.01,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            plan: VecDeque::new(),
+            simulating: false,
+        }
+    }
+
+    fn is_choosing_action(state: &PandemicGameState, agent_idx: usize) -> bool {
+        matches!(
+            state.next_decision(),
+            PendingDecision::ChooseAction { agent_idx: i, .. } if i == agent_idx
+        )
+    }
+
+    // resolve discards and event windows until someone has to choose an action
+    fn finish_decisions(&mut self, state: &mut PandemicGameState) {
+        while !matches!(
+            state.next_decision(),
+            PendingDecision::ChooseAction { .. } | PendingDecision::GameOver(_)
+        ) {
+            let decision = decide(self, state).expect("game is not over");
+            state
+                .resolve(decision)
+                .expect("rollout decisions are legal");
+        }
+    }
+
+    // A copy of state to simulate on. The agent can't know the order of the decks, so the cards no
+    // player can see are reshuffled, the same way for every choice compared against each other
+    fn sample_state(state: &PandemicGameState, seed: u64) -> PandemicGameState {
+        let mut sim_state = state.clone();
+        sim_state.sample_hidden_cards(seed);
+        sim_state
+    }
+
+    pub fn run_simulation_action(
+        &mut self,
+        state: &PandemicGameState,
+        action: Action,
+        seed: u64,
+    ) -> (f64, PandemicGameState) {
+        let mut sim_state = Self::sample_state(state, seed);
+        sim_state
+            .resolve(Decision::Action(action))
+            .expect("simulated actions are legal");
+        self.finish_decisions(&mut sim_state);
+        (estimate_board_state_score(&sim_state), sim_state)
+    }
+
+    // score of the board once decision and everything up to the next action are resolved
+    fn run_simulation_decision(
+        &mut self,
+        state: &PandemicGameState,
+        decision: Decision,
+        seed: u64,
+    ) -> f64 {
+        let mut sim_state = Self::sample_state(state, seed);
+        sim_state
+            .resolve(decision)
+            .expect("simulated decisions are legal");
+        self.finish_decisions(&mut sim_state);
+        estimate_board_state_score(&sim_state)
+    }
+
+    // every event agent_idxs could play now, with one forecast order instead of all of them
+    fn candidate_events(&mut self, state: &PandemicGameState, agent_idxs: &[usize]) -> Vec<Action> {
+        let mut events = Vec::new();
+        for &agent_idx in agent_idxs {
+            for action in state.gen_event_actions(agent_idx) {
+                let is_forecast = matches!(
+                    action,
+                    Action::PlayEvent {
+                        event: EventAction::Forecast(_),
+                        ..
+                    }
+                );
+                if !is_forecast {
+                    events.push(action);
+                }
+            }
+            if state
+                .playable_events(agent_idx)
+                .contains(&EventCard::Forecast)
+            {
+                let cards = state.forecast_cards();
+                events.push(Action::PlayEvent {
+                    agent_idx,
+                    event: EventAction::Forecast(
+                        self.choose_forecast_order(state, agent_idx, &cards),
+                    ),
+                });
+            }
+        }
+        events
+    }
+
+    // The best max_simulated_branching_factor ways to play the rest of the current turn, best
+    // first
+    pub fn run_simulation_turn(&mut self, state: &PandemicGameState) -> Vec<SimulatedTurn> {
+        let agent_idx = state.current_player_i as usize;
+        let mut partial_turns = vec![SimulatedTurn {
+            state: state.clone(),
+            agent_idx,
+            actions: vec![],
+            score: estimate_board_state_score(state),
+        }];
+        let nactions = state.actions_left;
+        for i in 0..nactions {
+            let mut new_partial_turns = Vec::new();
+            for turn in &partial_turns {
+                if !Self::is_choosing_action(&turn.state, agent_idx) {
+                    new_partial_turns.push(turn.clone());
+                    continue;
+                }
+                let seed = self.rng.gen();
+                for action in turn.state.legal_actions(agent_idx) {
+                    if !action.uses_action() {
+                        continue;
+                    }
+                    let mut actions = turn.actions.clone();
+                    actions.push(action.clone());
+                    let (score, new_state) = self.run_simulation_action(&turn.state, action, seed);
+                    new_partial_turns.push(SimulatedTurn {
+                        state: new_state,
+                        agent_idx,
+                        actions,
+                        score,
+                    });
+                }
+            }
+            // shuffle so that different actions of the same score get selected
+            new_partial_turns.shuffle(&mut self.rng);
+            new_partial_turns.sort_by(|a, b| a.score.total_cmp(&b.score));
+            let branching_factor = if i + 1 == nactions {
+                self.max_simulated_branching_factor
+            } else {
+                self.max_estimated_branching_factor
+            };
+            let pruned = new_partial_turns.split_off(branching_factor.min(new_partial_turns.len()));
+            // choose a random action with some probability to do exploration sometimes instead of
+            // only greedy selection
+            if !pruned.is_empty() && self.rng.gen_bool(self.random_action_prob) {
+                let explored = pruned.choose(&mut self.rng).unwrap().clone();
+                *new_partial_turns.last_mut().unwrap() = explored;
+            }
+            partial_turns = new_partial_turns;
+        }
+        partial_turns
+    }
+
+    // The best sequence of n turns found, starting with the current one
+    pub fn run_simulation_turn_n(
+        &mut self,
+        n: usize,
+        state: &PandemicGameState,
+    ) -> Vec<SimulatedTurn> {
+        assert!(n >= 1);
+        // every simulated turn, with the index of the turn it follows in the previous round
+        let mut rounds: Vec<Vec<(SimulatedTurn, usize)>> = Vec::new();
+        for i in 0..n {
+            let mut round = Vec::new();
+            if i == 0 {
+                for turn in self.run_simulation_turn(state) {
+                    round.push((turn, 0));
+                }
+            } else {
+                for (j, (prev_turn, _)) in rounds[i - 1].iter().enumerate() {
+                    if matches!(prev_turn.state.phase, TurnPhase::GameOver(_)) {
+                        continue;
+                    }
+                    for turn in self.run_simulation_turn(&prev_turn.state) {
+                        round.push((turn, j));
+                    }
+                }
+                if round.is_empty() {
+                    break;
+                }
+            }
+            rounds.push(round);
+        }
+        // games that ended early are compared against the deepest round
+        let mut best: Option<(usize, usize)> = None;
+        for (i, round) in rounds.iter().enumerate() {
+            for (j, (turn, _)) in round.iter().enumerate() {
+                let is_last_round = i + 1 == rounds.len();
+                if !is_last_round && !matches!(turn.state.phase, TurnPhase::GameOver(_)) {
+                    continue;
+                }
+                let is_better = match best {
+                    Some((bi, bj)) => turn.score < rounds[bi][bj].0.score,
+                    None => true,
+                };
+                if is_better {
+                    best = Some((i, j));
+                }
+            }
+        }
+        let (mut cur_round, mut cur_i) = best.expect("a turn always has an action");
+        let mut path = VecDeque::new();
+        loop {
+            let (turn, parent_i) = &rounds[cur_round][cur_i];
+            path.push_front(turn.clone());
+            if cur_round == 0 {
+                break;
+            }
+            cur_round -= 1;
+            cur_i = *parent_i;
+        }
+        path.into()
+    }
+}
+
+fn city_cubes(state: &PandemicGameState, city: CityCard) -> u32 {
+    state.get_n_disease_cubes(city, state.config.board.disease(city))
+}
+
+// Discards event cards last, and otherwise cards of cured diseases and then of the diseases the
+// hand has the fewest cards of
+pub fn least_useful_cards(
+    state: &PandemicGameState,
+    agent_idx: usize,
+    ndiscards: usize,
+) -> Vec<PlayerCard> {
+    let hand = state.player_hand(agent_idx);
+    let disease_count = |disease: Disease| {
+        hand.iter()
+            .filter(|c| matches!(c, PlayerCard::CityCard(city) if state.config.board.disease(*city) == disease))
+            .count()
+    };
+    let mut cards = hand.to_vec();
+    cards.sort_by_key(|card| match card {
+        PlayerCard::CityCard(city) => {
+            let disease = state.config.board.disease(*city);
+            (1, !state.is_cured(disease) as usize, disease_count(disease))
+        }
+        _ => (2, 0, 0),
+    });
+    cards.truncate(ndiscards);
+    cards
+}
+
+impl Controller for SingleAgentRolloutAI {
+    fn choose_action(&mut self, state: &PandemicGameState, agent_idx: usize) -> Action {
+        let plan_is_legal = match self.plan.front() {
+            Some(action) => {
+                self.plan.len() == state.actions_left as usize
+                    && state.legal_actions(agent_idx).contains(action)
+            }
+            None => false,
+        };
+        if !plan_is_legal {
+            self.simulating = true;
+            let path = self.run_simulation_turn_n(self.lookahead_turns, state);
+            self.simulating = false;
+            self.plan = path[0].actions.clone().into();
+        }
+        self.plan.pop_front().expect("a turn always has an action")
+    }
+
+    fn choose_discards(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idx: usize,
+        ndiscards: usize,
+    ) -> Vec<PlayerCard> {
+        least_useful_cards(state, agent_idx, ndiscards)
+    }
+
+    fn choose_event(
+        &mut self,
+        state: &PandemicGameState,
+        agent_idxs: &[usize],
+        window: EventWindow,
+    ) -> Option<Decision> {
+        // the board score can't change before the next action, so only windows before cards are
+        // drawn are worth simulating
+        if self.simulating || matches!(window, EventWindow::BeforeAction { .. }) {
+            return None;
+        }
+        self.simulating = true;
+        let seed = self.rng.gen();
+        let mut best_score = self.run_simulation_decision(state, Decision::Pass, seed);
+        let mut best_event = None;
+        for action in self.candidate_events(state, agent_idxs) {
+            let score = self.run_simulation_decision(state, Decision::Action(action.clone()), seed);
+            if score < best_score {
+                best_score = score;
+                best_event = Some(action);
+            }
+        }
+        self.simulating = false;
+        best_event.map(Decision::Action)
+    }
+
+    // cities with the fewest cubes of their own disease are drawn first
+    fn choose_forecast_order(
+        &mut self,
+        state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..cards.len()).collect();
+        order.sort_by_key(|i| city_cubes(state, cards[*i]));
+        order
+    }
+
+    // the city with the most cubes of its own disease is the worst to draw again
+    fn choose_resilient_population_target(
+        &mut self,
+        state: &PandemicGameState,
+        _agent_idx: usize,
+        cards: &[CityCard],
+    ) -> CityCard {
+        *cards
+            .iter()
+            .max_by_key(|city| city_cubes(state, **city))
+            .expect("resilient population needs a target")
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentName;
+use crate::board::STANDARD_MAP;
+use crate::game_enums::EventCard;
+use crate::map_validation::DEFAULT_DISEASE_CUBES;
+use crate::pandemic_game::PandemicGameConfig;
+
+pub const STANDARD_INFECTION_RATES: [usize; 7] = [2, 2, 2, 3, 3, 4, 4];
+
+// The rulebook's difficulty levels. Each preset fixes the number of epidemics and uses the
+// standard infection rate track, anything else is Custom
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum Difficulty {
+    Introductory,
+    Standard,
+    Heroic,
+    Custom,
+}
+
+impl Difficulty {
+    pub fn nepidemics(self) -> Option<i32> {
+        match self {
+            Difficulty::Introductory => Some(4),
+            Difficulty::Standard => Some(5),
+            Difficulty::Heroic => Some(6),
+            Difficulty::Custom => None,
+        }
+    }
+
+    // the preset these settings amount to
+    pub fn of(nepidemics: i32, infection_rates: &[usize]) -> Difficulty {
+        if infection_rates != STANDARD_INFECTION_RATES {
+            return Difficulty::Custom;
+        }
+        [
+            Difficulty::Introductory,
+            Difficulty::Standard,
+            Difficulty::Heroic,
+        ]
+        .into_iter()
+        .find(|d| d.nepidemics() == Some(nepidemics))
+        .unwrap_or(Difficulty::Custom)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "introductory" => Result::Ok(Difficulty::Introductory),
+            "standard" => Result::Ok(Difficulty::Standard),
+            "heroic" => Result::Ok(Difficulty::Heroic),
+            "custom" => Result::Ok(Difficulty::Custom),
+            other => Result::Err(format!("unknown difficulty {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum StartingPlayer {
+    Random,
+    // seat index
+    Fixed(u32),
+}
+
+// Every setting of a config that changes how the game plays, kept with results so runs under
+// different rules aren't compared by mistake
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub map: String,
+    pub difficulty: Difficulty,
+    pub nplayers: i32,
+    pub nepidemics: i32,
+    pub infection_rates: Vec<usize>,
+    pub ncards_to_draw: u32,
+    pub hand_limit: usize,
+    pub max_research_stations: usize,
+    // the supply of each disease
+    pub max_disease_cubes: Vec<u32>,
+    pub max_outbreaks: u32,
+    // event cards in the player deck, and whether anyone plays them
+    pub events: Vec<EventCard>,
+    pub do_events: bool,
+    pub starting_player: StartingPlayer,
+    // roles dealt from
+    pub roles: Vec<AgentName>,
+}
+
+impl PandemicGameConfig {
+    pub fn ruleset(&self) -> Ruleset {
+        Ruleset {
+            map: self.board.name.clone(),
+            difficulty: self.difficulty,
+            nplayers: self.nplayers,
+            nepidemics: self.nepidemics,
+            infection_rates: self.infection_rates.clone(),
+            ncards_to_draw: self.ncards_to_draw,
+            hand_limit: self.hand_limit,
+            max_research_stations: self.max_research_stations,
+            max_disease_cubes: self.max_disease_cubes.clone(),
+            max_outbreaks: self.max_outbreaks,
+            events: self.events.clone(),
+            do_events: self.do_events,
+            starting_player: self.starting_player,
+            roles: self.roles.clone(),
+        }
+    }
+}
+
+// one line, only the settings that differ from the rulebook besides players and difficulty
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?}, {} players, {} epidemics",
+            self.difficulty, self.nplayers, self.nepidemics
+        )?;
+        if self.map != STANDARD_MAP {
+            write!(f, ", map {}", self.map)?;
+        }
+        if self.infection_rates != STANDARD_INFECTION_RATES {
+            write!(f, ", infection rates {:?}", self.infection_rates)?;
+        }
+        if self.ncards_to_draw != 2 {
+            write!(f, ", draw {}", self.ncards_to_draw)?;
+        }
+        if self.hand_limit != 7 {
+            write!(f, ", hand limit {}", self.hand_limit)?;
+        }
+        if self.max_research_stations != 6 {
+            write!(f, ", {} research stations", self.max_research_stations)?;
+        }
+        if self
+            .max_disease_cubes
+            .iter()
+            .any(|n| *n != DEFAULT_DISEASE_CUBES)
+        {
+            write!(f, ", cubes {:?}", self.max_disease_cubes)?;
+        }
+        if self.max_outbreaks != 8 {
+            write!(f, ", {} outbreaks", self.max_outbreaks)?;
+        }
+        if !self.do_events {
+            write!(f, ", events off")?;
+        } else if self.events.len() != std::mem::variant_count::<EventCard>() {
+            write!(f, ", events {:?}", self.events)?;
+        }
+        if let StartingPlayer::Fixed(seat) = self.starting_player {
+            write!(f, ", player {} starts", seat)?;
+        }
+        if self.roles.len() != std::mem::variant_count::<AgentName>() {
+            write!(f, ", roles {:?}", self.roles)?;
+        }
+        Result::Ok(())
+    }
+}
+
+// a comma separated list of role names, as the command line tools take them
+pub fn parse_roles(s: &str) -> Result<Vec<AgentName>, String> {
+    s.split(',')
+        .filter(|role| !role.is_empty())
+        .map(|role| role.parse().map_err(|_| format!("unknown role {}", role)))
+        .collect()
+}
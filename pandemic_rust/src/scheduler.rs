@@ -0,0 +1,817 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::{Action, EventAction};
+use crate::agent::{ActionEndState, ActionError};
+use crate::board::{Board, CityCard, NamedOnBoard};
+use crate::game_enums::{EventCard, GameEnd, PlayerCard};
+use crate::pandemic_game::PandemicGameState;
+
+pub const ACTIONS_PER_TURN: u32 = 4;
+
+// Where the current turn is. Every action and every phase other than GameOver starts with an event
+// window, which stays open until all players pass
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum TurnPhase {
+    Actions,
+    // before drawing the next of cards_left player cards
+    Draw { cards_left: u32 },
+    // after an epidemic's infect step, before intensifying
+    EpidemicIntensify { cards_left: u32 },
+    // before drawing infection cards
+    Infect,
+    GameOver(GameEnd),
+}
+
+// The points where the rules let players play events, outside of the current player's own
+// actions
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum EventWindow {
+    // before each of the current player's actions, including the start of their turn. Only the
+    // other players are asked, the current player plays events as actions
+    BeforeAction { actions_left: u32 },
+    BeforeDraw { cards_left: u32 },
+    // the epidemic's card is in the infection discard and can still be removed by Resilient
+    // Population before it is shuffled back on top
+    EpidemicIntensify,
+    // last chance for One Quiet Night
+    BeforeInfect,
+}
+
+// What the game needs from a controller before it can continue
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum PendingDecision {
+    ChooseAction {
+        agent_idx: usize,
+        actions_left: u32,
+    },
+    // agent_idx is over the hand limit, and discards ndiscards cards or plays events from their
+    // hand first
+    ChooseDiscards {
+        agent_idx: usize,
+        ndiscards: usize,
+    },
+    // any of agent_idxs may play an event, or everyone passes
+    ChooseEvent {
+        agent_idxs: Vec<usize>,
+        window: EventWindow,
+    },
+    // new order for the forecasted cards, which are listed top of the deck first
+    ChooseForecastOrder {
+        agent_idx: usize,
+        cards: Vec<CityCard>,
+    },
+    ChooseResilientPopulationTarget {
+        agent_idx: usize,
+        cards: Vec<CityCard>,
+    },
+    GameOver(GameEnd),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum Decision {
+    // a turn action, or a fully specified Action::PlayEvent
+    Action(Action),
+    Discard(Vec<PlayerCard>),
+    // play Forecast or Resilient Population and choose how to resolve it next
+    PlayEvent { agent_idx: usize, event: EventCard },
+    Pass,
+    ForecastOrder(Vec<usize>),
+    ResilientPopulationTarget(CityCard),
+}
+
+fn decision_err(msg: String) -> Result<(), ActionError> {
+    Result::Err(ActionError::new(msg))
+}
+
+impl PandemicGameState {
+    pub fn next_decision(&self) -> PendingDecision {
+        if let TurnPhase::GameOver(game_end) = self.phase {
+            return PendingDecision::GameOver(game_end);
+        }
+        if let Some((agent_idx, event)) = self.pending_event {
+            return match event {
+                EventCard::Forecast => PendingDecision::ChooseForecastOrder {
+                    agent_idx,
+                    cards: self.forecast_cards(),
+                },
+                _ => PendingDecision::ChooseResilientPopulationTarget {
+                    agent_idx,
+                    cards: self.infection_discard.clone(),
+                },
+            };
+        }
+        if let Some(agent_idx) = self.agent_over_hand_limit() {
+            return PendingDecision::ChooseDiscards {
+                agent_idx,
+                ndiscards: self.player_hand(agent_idx).len() - self.config.hand_limit,
+            };
+        }
+        match self.event_window() {
+            Some(window) if self.event_window_open => {
+                let agent_idxs = self.event_window_agents();
+                if !agent_idxs.is_empty() {
+                    return PendingDecision::ChooseEvent { agent_idxs, window };
+                }
+            }
+            _ => {}
+        }
+        PendingDecision::ChooseAction {
+            agent_idx: self.current_player_i as usize,
+            actions_left: self.actions_left,
+        }
+    }
+
+    pub fn event_window(&self) -> Option<EventWindow> {
+        match self.phase {
+            TurnPhase::Actions => Some(EventWindow::BeforeAction {
+                actions_left: self.actions_left,
+            }),
+            TurnPhase::Draw { cards_left } => Some(EventWindow::BeforeDraw { cards_left }),
+            TurnPhase::EpidemicIntensify { .. } => Some(EventWindow::EpidemicIntensify),
+            TurnPhase::Infect => Some(EventWindow::BeforeInfect),
+            TurnPhase::GameOver(_) => None,
+        }
+    }
+
+    // the players asked in the current event window
+    pub fn event_window_agents(&self) -> Vec<usize> {
+        let mut agent_idxs = self.agents_with_events();
+        if self.phase == TurnPhase::Actions {
+            agent_idxs.retain(|i| *i != self.current_player_i as usize);
+        }
+        agent_idxs
+    }
+
+    pub fn resolve(&mut self, decision: Decision) -> Result<(), ActionError> {
+        match (self.next_decision(), decision) {
+            (PendingDecision::GameOver(_), _) => {
+                return decision_err("game is over".to_string());
+            }
+            (PendingDecision::ChooseAction { agent_idx, .. }, Decision::Action(action)) => {
+                let uses_action = action.uses_action();
+                self.resolve_action(agent_idx, action)?;
+                if uses_action {
+                    self.actions_left -= 1;
+                    self.event_window_open = true;
+                }
+            }
+            (
+                pending @ (PendingDecision::ChooseEvent { .. }
+                | PendingDecision::ChooseDiscards { .. }),
+                Decision::Action(action),
+            ) => {
+                let (event_agent_idx, event) = match &action {
+                    Action::PlayEvent { agent_idx, event } => (*agent_idx, event.card()),
+                    _ => {
+                        return decision_err("only events can be played now".to_string());
+                    }
+                };
+                self.check_event_player(&pending, event_agent_idx, event)?;
+                self.resolve_action(event_agent_idx, action)?;
+            }
+            (
+                pending @ (PendingDecision::ChooseAction { .. }
+                | PendingDecision::ChooseEvent { .. }
+                | PendingDecision::ChooseDiscards { .. }),
+                Decision::PlayEvent { agent_idx, event },
+            ) => {
+                self.check_event_player(&pending, agent_idx, event)?;
+                if event != EventCard::Forecast && event != EventCard::ResilientPopulation {
+                    return decision_err(format!(
+                        "{:?} must be played as a fully specified Action::PlayEvent",
+                        event
+                    ));
+                }
+                if agent_idx >= self.agents.len()
+                    || !self.playable_events(agent_idx).contains(&event)
+                {
+                    return decision_err("player does not have event card".to_string());
+                }
+                self.pending_event = Some((agent_idx, event));
+            }
+            (PendingDecision::ChooseEvent { .. }, Decision::Pass) => {
+                self.event_window_open = false;
+            }
+            (
+                PendingDecision::ChooseDiscards {
+                    agent_idx,
+                    ndiscards,
+                },
+                Decision::Discard(cards),
+            ) => {
+                if cards.len() != ndiscards {
+                    return decision_err(format!("must discard exactly {} cards", ndiscards));
+                }
+                let mut hand = self.player_hand(agent_idx).to_vec();
+                for card in &cards {
+                    match hand.iter().position(|c| c == card) {
+                        Some(i) => {
+                            hand.swap_remove(i);
+                        }
+                        None => {
+                            return decision_err(format!(
+                                "{} not in hand",
+                                self.config.board.named(card)
+                            ))
+                        }
+                    }
+                }
+                for card in cards {
+                    self.discard_player_card(agent_idx, card)
+                        .expect("discards are in the hand");
+                }
+            }
+            (
+                PendingDecision::ChooseForecastOrder { agent_idx, .. },
+                Decision::ForecastOrder(order),
+            ) => {
+                self.resolve_action(
+                    agent_idx,
+                    Action::PlayEvent {
+                        agent_idx,
+                        event: EventAction::Forecast(order),
+                    },
+                )?;
+                self.pending_event = None;
+            }
+            (
+                PendingDecision::ChooseResilientPopulationTarget { agent_idx, .. },
+                Decision::ResilientPopulationTarget(city),
+            ) => {
+                self.resolve_action(
+                    agent_idx,
+                    Action::PlayEvent {
+                        agent_idx,
+                        event: EventAction::ResilientPopulation(city),
+                    },
+                )?;
+                self.pending_event = None;
+            }
+            (pending, decision) => {
+                return decision_err(format!(
+                    "{} does not resolve {}",
+                    self.config.board.named(&decision),
+                    self.config.board.named(&pending)
+                ));
+            }
+        }
+        self.advance();
+        Result::Ok(())
+    }
+
+    fn resolve_action(&mut self, agent_idx: usize, action: Action) -> Result<(), ActionError> {
+        match self.do_action(agent_idx, action) {
+            Result::Ok(_) => Result::Ok(()),
+            Result::Err(ActionEndState::Ok(game_end)) => {
+                self.phase = TurnPhase::GameOver(game_end);
+                Result::Ok(())
+            }
+            Result::Err(ActionEndState::Err(e)) => Result::Err(e),
+        }
+    }
+
+    // only the players an event window asks can play events in it, and only the player over the
+    // hand limit can play them then, from their hand
+    fn check_event_player(
+        &self,
+        pending: &PendingDecision,
+        agent_idx: usize,
+        event: EventCard,
+    ) -> Result<(), ActionError> {
+        match pending {
+            PendingDecision::ChooseEvent { agent_idxs, .. } if !agent_idxs.contains(&agent_idx) => {
+                decision_err(format!("player {} can't play events now", agent_idx))
+            }
+            PendingDecision::ChooseDiscards {
+                agent_idx: over_limit_idx,
+                ..
+            } if agent_idx != *over_limit_idx
+                || !self.hand_limit_events(agent_idx).contains(&event) =>
+            {
+                decision_err(format!(
+                    "player {} must discard first, or play an event from their hand",
+                    over_limit_idx
+                ))
+            }
+            _ => Result::Ok(()),
+        }
+    }
+
+    pub fn agent_over_hand_limit(&self) -> Option<usize> {
+        (0..self.agents.len()).find(|i| self.player_hand(*i).len() > self.config.hand_limit)
+    }
+
+    // event cards in agent_idx's hand that could be played instead of discarding
+    pub fn hand_limit_events(&self, agent_idx: usize) -> Vec<EventCard> {
+        if !self.config.do_events {
+            return vec![];
+        }
+        self.player_hand(agent_idx)
+            .iter()
+            .filter_map(|c| match c {
+                PlayerCard::EventCard(event) if self.event_has_targets(*event) => Some(*event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn agents_with_events(&self) -> Vec<usize> {
+        if !self.config.do_events {
+            return vec![];
+        }
+        (0..self.agents.len())
+            .filter(|i| !self.playable_events(*i).is_empty())
+            .collect()
+    }
+
+    // top of the infection deck first
+    pub fn forecast_cards(&self) -> Vec<CityCard> {
+        let top = self.infection_deck.len().saturating_sub(6);
+        self.infection_deck[top..].iter().rev().copied().collect()
+    }
+
+    // drawing the last card goes straight to infecting, with a single window in between
+    fn draw_phase(cards_left: u32) -> TurnPhase {
+        if cards_left == 0 {
+            TurnPhase::Infect
+        } else {
+            TurnPhase::Draw { cards_left }
+        }
+    }
+
+    fn enter_phase(&mut self, phase: TurnPhase) {
+        self.phase = phase;
+        self.event_window_open = true;
+    }
+
+    fn end_game_if_over(&mut self, result: Result<(), GameEnd>) -> bool {
+        match result {
+            Result::Ok(_) => false,
+            Result::Err(game_end) => {
+                self.phase = TurnPhase::GameOver(game_end);
+                true
+            }
+        }
+    }
+
+    // Run the game forward until it needs a decision
+    pub fn advance(&mut self) {
+        loop {
+            if self.pending_event.is_some() || self.agent_over_hand_limit().is_some() {
+                return;
+            }
+            let phase = self.phase;
+            match phase {
+                TurnPhase::GameOver(_) => return,
+                TurnPhase::Actions => {
+                    if self.actions_left > 0 {
+                        return;
+                    }
+                    self.enter_phase(Self::draw_phase(self.config.ncards_to_draw));
+                    continue;
+                }
+                _ => {}
+            }
+            if self.event_window_open && !self.event_window_agents().is_empty() {
+                return;
+            }
+            match phase {
+                TurnPhase::Draw { cards_left } => {
+                    let card = match self.draw_player_cards(1) {
+                        Result::Ok(cards) => cards[0],
+                        Result::Err(game_end) => {
+                            self.phase = TurnPhase::GameOver(game_end);
+                            continue;
+                        }
+                    };
+                    if card == PlayerCard::Epidemic {
+                        self.player_discard.push(card);
+                        let result = self.epidemic_increase_and_infect();
+                        if self.end_game_if_over(result) {
+                            continue;
+                        }
+                        self.enter_phase(TurnPhase::EpidemicIntensify {
+                            cards_left: cards_left - 1,
+                        });
+                    } else {
+                        self.player_hands[self.current_player_i as usize].push(card);
+                        self.enter_phase(Self::draw_phase(cards_left - 1));
+                    }
+                }
+                TurnPhase::EpidemicIntensify { cards_left } => {
+                    self.epidemic_intensify();
+                    self.enter_phase(Self::draw_phase(cards_left));
+                }
+                TurnPhase::Infect => {
+                    let result = self.do_infect_step();
+                    if !self.end_game_if_over(result) {
+                        self.incr_current_player();
+                        self.actions_left = ACTIONS_PER_TURN;
+                        self.enter_phase(TurnPhase::Actions);
+                    }
+                }
+                TurnPhase::Actions | TurnPhase::GameOver(_) => unreachable!(),
+            }
+        }
+    }
+}
+
+impl NamedOnBoard for Decision {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Decision::Action(action) => write!(f, "Action({})", board.named(action)),
+            Decision::Discard(cards) => write!(f, "Discard({})", board.named(cards)),
+            Decision::ResilientPopulationTarget(city) => {
+                write!(f, "ResilientPopulationTarget({})", board.named(city))
+            }
+            Decision::PlayEvent { .. } | Decision::Pass | Decision::ForecastOrder(_) => {
+                write!(f, "{:?}", self)
+            }
+        }
+    }
+}
+
+impl NamedOnBoard for PendingDecision {
+    fn fmt_named(&self, board: &Board, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PendingDecision::ChooseForecastOrder { agent_idx, cards } => write!(
+                f,
+                "ChooseForecastOrder {{ agent_idx: {}, cards: {} }}",
+                agent_idx,
+                board.named(cards)
+            ),
+            PendingDecision::ChooseResilientPopulationTarget { agent_idx, cards } => write!(
+                f,
+                "ChooseResilientPopulationTarget {{ agent_idx: {}, cards: {} }}",
+                agent_idx,
+                board.named(cards)
+            ),
+            PendingDecision::ChooseAction { .. }
+            | PendingDecision::ChooseDiscards { .. }
+            | PendingDecision::ChooseEvent { .. }
+            | PendingDecision::GameOver(_) => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::AgentName;
+    use crate::pandemic_game::{PandemicGameConfig, PandemicGameConfigBuilder};
+    use crate::ruleset::StartingPlayer;
+
+    // seat 0 starts. Nobody is dealt event cards, so event windows only open for the ones a test
+    // hands out
+    fn new_state(do_events: bool) -> PandemicGameState {
+        state_with(PandemicGameConfig::builder().do_events(do_events))
+    }
+
+    fn state_with(builder: PandemicGameConfigBuilder) -> PandemicGameState {
+        let config = builder
+            .seed(0)
+            .starting_player(StartingPlayer::Fixed(0))
+            .roles(&[
+                AgentName::Medic,
+                AgentName::Scientist,
+                AgentName::Researcher,
+                AgentName::Dispatcher,
+            ])
+            .build()
+            .unwrap();
+        let mut state = PandemicGameState::new(config);
+        for hand in state.player_hands.iter_mut() {
+            hand.retain(|c| !matches!(c, PlayerCard::EventCard(_)));
+        }
+        state
+    }
+
+    fn give(state: &mut PandemicGameState, agent_idx: usize, card: PlayerCard) {
+        state.player_deck.retain(|c| *c != card);
+        state.player_hands[agent_idx].push(card);
+    }
+
+    // drive to a neighbor and back until the turn's actions are used up
+    fn spend_actions(state: &mut PandemicGameState) {
+        let agent_idx = state.current_player_i as usize;
+        let here = state.player_location(agent_idx);
+        let neighbor = state.config.board.neighbors(here)[0];
+        while let PendingDecision::ChooseAction { actions_left, .. } = state.next_decision() {
+            let city = if actions_left % 2 == 0 {
+                neighbor
+            } else {
+                here
+            };
+            state
+                .resolve(Decision::Action(Action::Drive(city)))
+                .unwrap();
+            if state.phase != TurnPhase::Actions {
+                return;
+            }
+        }
+    }
+
+    fn expect_window(state: &PandemicGameState, agent_idxs: Vec<usize>, window: EventWindow) {
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseEvent { agent_idxs, window }
+        );
+    }
+
+    #[test]
+    fn phases_run_actions_draw_intensify_infect() {
+        let mut state = new_state(true);
+        give(
+            &mut state,
+            2,
+            PlayerCard::EventCard(EventCard::OneQuietNight),
+        );
+        // an epidemic, then a city card, are the next two draws
+        let i = state
+            .player_deck
+            .iter()
+            .position(|c| *c == PlayerCard::Epidemic)
+            .unwrap();
+        let epidemic = state.player_deck.remove(i);
+        let city = state
+            .player_deck
+            .iter()
+            .rposition(|c| matches!(c, PlayerCard::CityCard(_)))
+            .unwrap();
+        let city = state.player_deck.remove(city);
+        state.player_deck.push(city);
+        state.player_deck.push(epidemic);
+
+        for actions_left in (1..=ACTIONS_PER_TURN).rev() {
+            expect_window(&state, vec![2], EventWindow::BeforeAction { actions_left });
+            state.resolve(Decision::Pass).unwrap();
+            assert_eq!(
+                state.next_decision(),
+                PendingDecision::ChooseAction {
+                    agent_idx: 0,
+                    actions_left
+                }
+            );
+            let here = state.player_location(0);
+            let neighbor = state.config.board.neighbors(here)[0];
+            state
+                .resolve(Decision::Action(Action::Drive(neighbor)))
+                .unwrap();
+        }
+
+        assert_eq!(state.phase, TurnPhase::Draw { cards_left: 2 });
+        expect_window(&state, vec![2], EventWindow::BeforeDraw { cards_left: 2 });
+        state.resolve(Decision::Pass).unwrap();
+
+        assert_eq!(state.phase, TurnPhase::EpidemicIntensify { cards_left: 1 });
+        assert_eq!(state.infection_rate_i, 1);
+        assert_eq!(state.epidemic_reports.len(), 1);
+        assert_eq!(state.player_discard, vec![PlayerCard::Epidemic]);
+        expect_window(&state, vec![2], EventWindow::EpidemicIntensify);
+        state.resolve(Decision::Pass).unwrap();
+
+        assert!(state.infection_discard.is_empty());
+        assert_eq!(state.phase, TurnPhase::Draw { cards_left: 1 });
+        expect_window(&state, vec![2], EventWindow::BeforeDraw { cards_left: 1 });
+        state.resolve(Decision::Pass).unwrap();
+
+        assert!(state.player_hand(0).contains(&city));
+        assert_eq!(state.phase, TurnPhase::Infect);
+        expect_window(&state, vec![2], EventWindow::BeforeInfect);
+        let infection_deck = state.infection_deck.len();
+        state.resolve(Decision::Pass).unwrap();
+
+        assert_eq!(
+            state.infection_deck.len(),
+            infection_deck - state.infection_rate()
+        );
+        assert_eq!(state.infection_discard.len(), state.infection_rate());
+        assert_eq!(state.current_player_i, 1);
+        assert_eq!(state.phase, TurnPhase::Actions);
+        assert_eq!(state.actions_left, ACTIONS_PER_TURN);
+    }
+
+    #[test]
+    fn before_action_window_leaves_out_the_current_player() {
+        let mut state = new_state(true);
+        give(
+            &mut state,
+            0,
+            PlayerCard::EventCard(EventCard::OneQuietNight),
+        );
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseAction {
+                agent_idx: 0,
+                actions_left: ACTIONS_PER_TURN
+            }
+        );
+
+        give(&mut state, 3, PlayerCard::EventCard(EventCard::Airlift));
+        expect_window(
+            &state,
+            vec![3],
+            EventWindow::BeforeAction {
+                actions_left: ACTIONS_PER_TURN,
+            },
+        );
+        let quiet_night = Decision::Action(Action::PlayEvent {
+            agent_idx: 0,
+            event: EventAction::OneQuietNight,
+        });
+        assert!(state.resolve(quiet_night.clone()).is_err());
+        assert!(!state.skip_next_infect_cities);
+        state.resolve(Decision::Pass).unwrap();
+        state.resolve(quiet_night).unwrap();
+        assert!(state.skip_next_infect_cities);
+        // events don't use an action, and don't reopen the window
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseAction {
+                agent_idx: 0,
+                actions_left: ACTIONS_PER_TURN
+            }
+        );
+
+        // in every other window the current player is asked too
+        state.player_hands[3].clear();
+        give(&mut state, 0, PlayerCard::EventCard(EventCard::Airlift));
+        spend_actions(&mut state);
+        expect_window(&state, vec![0], EventWindow::BeforeDraw { cards_left: 2 });
+    }
+
+    #[test]
+    fn game_over_stops_the_game() {
+        let mut state = new_state(false);
+        // too few player cards to draw
+        state.player_deck.truncate(1);
+        spend_actions(&mut state);
+        assert_eq!(state.phase, TurnPhase::GameOver(GameEnd::PlayerDeckLimit));
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::GameOver(GameEnd::PlayerDeckLimit)
+        );
+        let here = state.player_location(0);
+        let neighbor = state.config.board.neighbors(here)[0];
+        for decision in [
+            Decision::Action(Action::Drive(neighbor)),
+            Decision::Pass,
+            Decision::Discard(vec![]),
+        ] {
+            assert!(state.resolve(decision).is_err());
+        }
+        let before = state.clone();
+        state.advance();
+        assert_eq!(state.phase, before.phase);
+        assert_eq!(state.player_locations, before.player_locations);
+        assert_eq!(state.current_player_i, 0);
+    }
+
+    // the next n player cards are city cards
+    fn stack_city_cards(state: &mut PandemicGameState, n: usize) -> Vec<PlayerCard> {
+        let cards: Vec<PlayerCard> = state
+            .player_deck
+            .iter()
+            .filter(|c| matches!(c, PlayerCard::CityCard(_)))
+            .take(n)
+            .copied()
+            .collect();
+        state.player_deck.retain(|c| !cards.contains(c));
+        state.player_deck.extend(cards.iter());
+        cards
+    }
+
+    #[test]
+    fn discards_down_to_a_configured_hand_limit() {
+        let mut state = state_with(PandemicGameConfig::builder().hand_limit(4).do_events(false));
+        let card = stack_city_cards(&mut state, 1)[0];
+        give(&mut state, 0, card);
+        stack_city_cards(&mut state, 2);
+        assert_eq!(state.player_hand(0).len(), 3);
+        spend_actions(&mut state);
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseDiscards {
+                agent_idx: 0,
+                ndiscards: 1
+            }
+        );
+        assert_eq!(state.phase, TurnPhase::Infect);
+
+        let hand = state.player_hand(0).to_vec();
+        let not_in_hand = state.player_hand(1)[0];
+        for decision in [
+            Decision::Discard(vec![not_in_hand]),
+            Decision::Discard(vec![]),
+            Decision::Discard(vec![hand[0], hand[1]]),
+            Decision::Pass,
+        ] {
+            assert!(state.resolve(decision).is_err());
+        }
+        assert_eq!(state.player_hand(0), &hand[..]);
+        assert!(state.player_hand(1).contains(&not_in_hand));
+
+        state.resolve(Decision::Discard(vec![hand[2]])).unwrap();
+        assert_eq!(state.player_hand(0).len(), 4);
+        assert!(!state.player_hand(0).contains(&hand[2]));
+        assert_eq!(state.player_discard.last(), Some(&hand[2]));
+        // the turn carries on once the hand is down to the limit
+        assert_eq!(state.current_player_i, 1);
+    }
+
+    #[test]
+    fn event_played_instead_of_discarding() {
+        let mut state = state_with(PandemicGameConfig::builder().hand_limit(4));
+        for card in stack_city_cards(&mut state, 2) {
+            give(&mut state, 0, card);
+        }
+        give(
+            &mut state,
+            0,
+            PlayerCard::EventCard(EventCard::OneQuietNight),
+        );
+        give(&mut state, 1, PlayerCard::EventCard(EventCard::Airlift));
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseDiscards {
+                agent_idx: 0,
+                ndiscards: 1
+            }
+        );
+
+        // only the player over the limit can play an event
+        let here = state.player_location(0);
+        let neighbor = state.config.board.neighbors(here)[0];
+        assert!(state
+            .resolve(Decision::Action(Action::PlayEvent {
+                agent_idx: 1,
+                event: EventAction::Airlift {
+                    agent_idx: 0,
+                    city: neighbor
+                },
+            }))
+            .is_err());
+        assert_eq!(state.player_location(0), here);
+
+        let hand = state.player_hand(0).to_vec();
+        state
+            .resolve(Decision::Action(Action::PlayEvent {
+                agent_idx: 0,
+                event: EventAction::OneQuietNight,
+            }))
+            .unwrap();
+        assert!(state.skip_next_infect_cities);
+        assert_eq!(state.player_hand(0).len(), 4);
+        assert!(hand
+            .iter()
+            .filter(|c| matches!(c, PlayerCard::CityCard(_)))
+            .all(|c| state.player_hand(0).contains(c)));
+        assert_eq!(
+            state.player_discard.last(),
+            Some(&PlayerCard::EventCard(EventCard::OneQuietNight))
+        );
+        assert!(!matches!(
+            state.next_decision(),
+            PendingDecision::ChooseDiscards { .. }
+        ));
+    }
+
+    #[test]
+    fn forecast_asks_for_its_order_next() {
+        let mut state = new_state(true);
+        give(&mut state, 1, PlayerCard::EventCard(EventCard::Forecast));
+        state
+            .resolve(Decision::PlayEvent {
+                agent_idx: 1,
+                event: EventCard::Forecast,
+            })
+            .unwrap();
+        let cards = state.forecast_cards();
+        assert_eq!(
+            state.next_decision(),
+            PendingDecision::ChooseForecastOrder {
+                agent_idx: 1,
+                cards: cards.clone()
+            }
+        );
+
+        for order in [vec![], vec![0, 1, 2], vec![0; 6]] {
+            assert!(state.resolve(Decision::ForecastOrder(order)).is_err());
+        }
+        assert!(state
+            .player_hand(1)
+            .contains(&PlayerCard::EventCard(EventCard::Forecast)));
+
+        state
+            .resolve(Decision::ForecastOrder((0..6).rev().collect()))
+            .unwrap();
+        let reversed: Vec<CityCard> = cards.into_iter().rev().collect();
+        assert_eq!(state.forecast_cards(), reversed);
+        assert!(!state
+            .player_hand(1)
+            .contains(&PlayerCard::EventCard(EventCard::Forecast)));
+        assert!(state.pending_event.is_none());
+    }
+}
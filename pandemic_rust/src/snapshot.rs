@@ -0,0 +1,187 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pandemic_game::PandemicGameState;
+
+// A snapshot is a whole game as JSON, config and board included, so a position can be checkpointed
+// or attached to a bug report and picked up exactly where it was, RNG and all. The version changes
+// whenever the format does, and snapshots from another version are refused rather than misread
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    version: u32,
+    state: &'a PandemicGameState,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    state: PandemicGameState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    Io(String),
+    // not JSON, or not shaped like a snapshot
+    Format(String),
+    Version(u32),
+    // a snapshot that parses but isn't a consistent game, see check_invariants
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "{}", e),
+            SnapshotError::Format(e) => write!(f, "not a snapshot: {}", e),
+            SnapshotError::Version(version) => write!(
+                f,
+                "snapshot version {}, this build reads version {}",
+                version, SNAPSHOT_VERSION
+            ),
+            SnapshotError::Invalid(violations) => {
+                write!(f, "inconsistent snapshot: {}", violations.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl PandemicGameState {
+    pub fn to_snapshot_json(&self) -> String {
+        let snapshot = SnapshotRef {
+            version: SNAPSHOT_VERSION,
+            state: self,
+        };
+        serde_json::to_string_pretty(&snapshot).unwrap()
+    }
+
+    pub fn from_snapshot_json(json: &str) -> Result<PandemicGameState, SnapshotError> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| SnapshotError::Format(e.to_string()))?;
+        // checked on its own first, a snapshot from another version may not parse as this one's
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or(SnapshotError::Format("no version".to_string()))?;
+        if version != SNAPSHOT_VERSION as u64 {
+            return Result::Err(SnapshotError::Version(version as u32));
+        }
+        let snapshot: Snapshot =
+            serde_json::from_value(value).map_err(|e| SnapshotError::Format(e.to_string()))?;
+        let violations = snapshot.state.check_invariants();
+        if !violations.is_empty() {
+            return Result::Err(SnapshotError::Invalid(violations));
+        }
+        Result::Ok(snapshot.state)
+    }
+
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_snapshot_json() + "\n")
+            .map_err(|e| SnapshotError::Io(format!("can't write {}: {}", path.display(), e)))
+    }
+
+    pub fn load_snapshot(path: impl AsRef<Path>) -> Result<PandemicGameState, SnapshotError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|e| SnapshotError::Io(format!("can't read {}: {}", path.display(), e)))?;
+        PandemicGameState::from_snapshot_json(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::{decide, play_game, RandomController};
+    use crate::pandemic_game::PandemicGameConfig;
+
+    // a game some way in, with epidemics and events played
+    fn game_in_progress(seed: u64) -> PandemicGameState {
+        let config = PandemicGameConfig::builder().seed(seed).build().unwrap();
+        let mut state = PandemicGameState::new(config);
+        let mut controller = RandomController::new(seed);
+        for _ in 0..40 {
+            match decide(&mut controller, &state) {
+                Some(decision) => state.resolve(decision).unwrap(),
+                None => break,
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn round_trip_continues_the_same_game() {
+        for seed in 0..5 {
+            let state = game_in_progress(seed);
+            let json = state.to_snapshot_json();
+            let mut loaded = PandemicGameState::from_snapshot_json(&json).unwrap();
+            assert_eq!(loaded.to_snapshot_json(), json);
+
+            let mut original = state.clone();
+            let end = play_game(&mut RandomController::new(100 + seed), &mut original);
+            let loaded_end = play_game(&mut RandomController::new(100 + seed), &mut loaded);
+            assert_eq!(end.unwrap(), loaded_end.unwrap());
+            assert_eq!(original.to_snapshot_json(), loaded.to_snapshot_json());
+        }
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let state = game_in_progress(7);
+        let path = std::env::temp_dir().join(format!("snapshot-test-{}.json", std::process::id()));
+        state.save_snapshot(&path).unwrap();
+        let loaded = PandemicGameState::load_snapshot(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().to_snapshot_json(), state.to_snapshot_json());
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let state = game_in_progress(1);
+        let mut value: serde_json::Value = serde_json::from_str(&state.to_snapshot_json()).unwrap();
+        value["version"] = serde_json::json!(SNAPSHOT_VERSION + 1);
+        assert_eq!(
+            PandemicGameState::from_snapshot_json(&value.to_string()).err(),
+            Some(SnapshotError::Version(SNAPSHOT_VERSION + 1))
+        );
+        // version 1 had no known infection runs, and loading one would forget Forecast's order
+        value["version"] = serde_json::json!(1);
+        assert_eq!(
+            PandemicGameState::from_snapshot_json(&value.to_string()).err(),
+            Some(SnapshotError::Version(1))
+        );
+        value["version"] = serde_json::json!(SNAPSHOT_VERSION);
+        value["state"]
+            .as_object_mut()
+            .unwrap()
+            .remove("known_infection_runs");
+        assert!(matches!(
+            PandemicGameState::from_snapshot_json(&value.to_string()),
+            Result::Err(SnapshotError::Format(_))
+        ));
+        value.as_object_mut().unwrap().remove("version");
+        assert!(matches!(
+            PandemicGameState::from_snapshot_json(&value.to_string()),
+            Result::Err(SnapshotError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn inconsistent_states_are_refused() {
+        let mut state = game_in_progress(2);
+        state.infection_deck.pop();
+        assert!(matches!(
+            PandemicGameState::from_snapshot_json(&state.to_snapshot_json()),
+            Result::Err(SnapshotError::Invalid(_))
+        ));
+        assert!(matches!(
+            PandemicGameState::load_snapshot("/nonexistent/snapshot.json"),
+            Result::Err(SnapshotError::Io(_))
+        ));
+    }
+}